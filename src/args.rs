@@ -1,3 +1,4 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use clap::Parser;
 
 use crate::clients::beacon::types::BlockId;
@@ -6,10 +7,28 @@ use crate::clients::beacon::types::BlockId;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
-    /// Slot to start indexing from
-    #[arg(short, long)]
+    /// Block to start indexing from: a slot, a block root or one of 'head', 'genesis',
+    /// 'finalized' and 'justified'
+    #[arg(short, long, conflicts_with = "from")]
     pub from_slot: Option<BlockId>,
 
+    /// Execution block number to start indexing from
+    #[arg(long, group = "from")]
+    pub from_block: Option<u64>,
+
+    /// Unix timestamp to start indexing from
+    #[arg(long, group = "from")]
+    pub from_timestamp: Option<u64>,
+
+    /// Date to start indexing from, either as `YYYY-MM-DD` or RFC 3339
+    #[arg(long, group = "from", value_parser = parse_date)]
+    pub from_date: Option<DateTime<Utc>>,
+
+    /// Block to stop indexing at, in the same formats as `--from-slot`. The indexer exits once
+    /// the range is indexed
+    #[arg(short, long, requires = "from_slot", conflicts_with = "from")]
+    pub to_slot: Option<BlockId>,
+
     /// Number of threads used for parallel indexing
     #[arg(short, long)]
     pub num_threads: Option<u32>,
@@ -18,3 +37,98 @@ pub struct Args {
     #[arg(short, long)]
     pub slots_per_save: Option<u32>,
 }
+
+fn parse_date(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date_time.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date_time| Utc.from_utc_datetime(&date_time))
+        .ok_or_else(|| format!("Invalid date {value}. Expected YYYY-MM-DD or RFC 3339."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date("2024-03-13").unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 13, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_rfc_3339_date() {
+        assert_eq!(
+            parse_date("2024-03-13T13:55:35Z").unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 13, 13, 55, 35).unwrap()
+        );
+        assert_eq!(
+            parse_date("2024-03-13T15:55:35+02:00").unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 13, 13, 55, 35).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_date() {
+        for date in [
+            "2024-02-30",
+            "13/03/2024",
+            "2024-03-13 13:55:35",
+            "1710338135",
+            "",
+        ] {
+            assert!(parse_date(date).is_err(), "{date}");
+        }
+    }
+
+    #[test]
+    fn test_from_arguments_are_exclusive() {
+        let args = Args::try_parse_from(["blob-indexer", "--from-date", "2024-03-13"]).unwrap();
+
+        assert!(args.from_date.is_some());
+        assert!(args.from_slot.is_none());
+        assert!(Args::try_parse_from([
+            "blob-indexer",
+            "--from-block",
+            "19426587",
+            "--from-timestamp",
+            "1710338135",
+        ])
+        .is_err());
+        assert!(Args::try_parse_from([
+            "blob-indexer",
+            "--from-slot",
+            "genesis",
+            "--from-date",
+            "2024-03-13",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_to_slot_requires_from_slot() {
+        assert!(Args::try_parse_from(["blob-indexer", "--to-slot", "head"]).is_err());
+        assert!(Args::try_parse_from([
+            "blob-indexer",
+            "--from-block",
+            "19426587",
+            "--to-slot",
+            "head",
+        ])
+        .is_err());
+        assert!(Args::try_parse_from([
+            "blob-indexer",
+            "--from-slot",
+            "genesis",
+            "--to-slot",
+            "head"
+        ])
+        .is_ok());
+    }
+}
@@ -0,0 +1,512 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+
+use crate::clients::beacon::{
+    types::{BlobScheduleEntry, Fork, Genesis, Spec},
+    BeaconClient,
+};
+
+/// Chain parameters fetched from the beacon node on startup.
+#[derive(Debug, Clone)]
+pub struct ChainSpec {
+    pub seconds_per_slot: u64,
+    pub slots_per_epoch: u64,
+    pub genesis_time: u64,
+    pub altair_fork_epoch: u64,
+    pub bellatrix_fork_epoch: u64,
+    pub capella_fork_epoch: u64,
+    pub deneb_fork_epoch: u64,
+    pub electra_fork_epoch: u64,
+    pub fulu_fork_epoch: u64,
+    /// Epochs nodes keep blob sidecars for before pruning them.
+    pub min_epochs_for_blob_sidecars_requests: u64,
+    pub max_blobs_per_block: u64,
+    pub max_blobs_per_block_electra: u64,
+    pub blob_schedule: Vec<BlobScheduleEntry>,
+}
+
+impl ChainSpec {
+    pub fn new(spec: Spec, genesis: Genesis) -> Self {
+        Self {
+            seconds_per_slot: spec.seconds_per_slot,
+            slots_per_epoch: spec.slots_per_epoch,
+            genesis_time: genesis.genesis_time,
+            altair_fork_epoch: spec.altair_fork_epoch,
+            bellatrix_fork_epoch: spec.bellatrix_fork_epoch,
+            capella_fork_epoch: spec.capella_fork_epoch,
+            deneb_fork_epoch: spec.deneb_fork_epoch,
+            electra_fork_epoch: spec.electra_fork_epoch,
+            fulu_fork_epoch: spec.fulu_fork_epoch,
+            min_epochs_for_blob_sidecars_requests: spec.min_epochs_for_blob_sidecars_requests,
+            max_blobs_per_block: spec.max_blobs_per_block,
+            max_blobs_per_block_electra: spec.max_blobs_per_block_electra,
+            blob_schedule: spec.blob_schedule,
+        }
+    }
+
+    pub async fn fetch(beacon_client: &BeaconClient) -> Result<Self> {
+        let (spec, genesis) =
+            tokio::try_join!(beacon_client.get_spec(), beacon_client.get_genesis())?;
+        let spec = spec.ok_or_else(|| anyhow!("Beacon node returned no chain spec"))?;
+        let genesis = genesis.ok_or_else(|| anyhow!("Beacon node returned no genesis"))?;
+
+        Ok(Self::new(spec, genesis))
+    }
+
+    /// First slot of the given epoch, if it fits in a slot number.
+    pub fn epoch_start_slot(&self, epoch: u64) -> Option<u32> {
+        epoch
+            .checked_mul(self.slots_per_epoch)
+            .and_then(|slot| u32::try_from(slot).ok())
+    }
+
+    /// First slot blobs can be found at. `None` if Deneb isn't scheduled on this chain.
+    pub fn deneb_fork_slot(&self) -> Option<u32> {
+        self.epoch_start_slot(self.deneb_fork_epoch)
+    }
+
+    /// Fork active at the given slot.
+    pub fn fork_at_slot(&self, slot: u32) -> Fork {
+        let epoch = slot as u64 / self.slots_per_epoch;
+        let fork_epochs = [
+            (Fork::Fulu, self.fulu_fork_epoch),
+            (Fork::Electra, self.electra_fork_epoch),
+            (Fork::Deneb, self.deneb_fork_epoch),
+            (Fork::Capella, self.capella_fork_epoch),
+            (Fork::Bellatrix, self.bellatrix_fork_epoch),
+            (Fork::Altair, self.altair_fork_epoch),
+        ];
+
+        fork_epochs
+            .into_iter()
+            .find(|(_, fork_epoch)| epoch >= *fork_epoch)
+            .map_or(Fork::Phase0, |(fork, _)| fork)
+    }
+
+    /// Maximum amount of blobs a block of the given fork and slot can carry. From Fulu onwards
+    /// it's set by the blob schedule, so it's `None` if the node doesn't expose it.
+    pub fn max_blobs_per_block(&self, fork: Fork, slot: u32) -> Option<u64> {
+        match fork {
+            Fork::Phase0 | Fork::Altair | Fork::Bellatrix | Fork::Capella => Some(0),
+            Fork::Deneb => Some(self.max_blobs_per_block),
+            Fork::Electra => Some(self.max_blobs_per_block_electra),
+            Fork::Fulu if self.blob_schedule.is_empty() => None,
+            Fork::Fulu => {
+                let epoch = slot as u64 / self.slots_per_epoch;
+
+                // The Electra limit holds until the first entry of the schedule
+                let max_blobs_per_block = self
+                    .blob_schedule
+                    .iter()
+                    .filter(|entry| entry.epoch <= epoch)
+                    .max_by_key(|entry| entry.epoch)
+                    .map_or(self.max_blobs_per_block_electra, |entry| {
+                        entry.max_blobs_per_block
+                    });
+
+                Some(max_blobs_per_block)
+            }
+        }
+    }
+
+    /// First slot at or after the given timestamp.
+    pub fn timestamp_to_slot(&self, timestamp: u64) -> u32 {
+        let slot = timestamp
+            .saturating_sub(self.genesis_time)
+            .div_ceil(self.seconds_per_slot);
+
+        u32::try_from(slot).unwrap_or(u32::MAX)
+    }
+
+    /// Slot the chain is at according to the wall clock.
+    pub fn current_slot(&self) -> u32 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let slot = now.saturating_sub(self.genesis_time) / self.seconds_per_slot;
+
+        u32::try_from(slot).unwrap_or(u32::MAX)
+    }
+
+    /// Lowest slot nodes are required to serve blob sidecars for. Blobs of older blocks may
+    /// have been pruned.
+    pub fn blobs_retention_start_slot(&self) -> u32 {
+        let current_epoch = self.current_slot() as u64 / self.slots_per_epoch;
+        let retention_start_epoch = current_epoch
+            .saturating_sub(self.min_epochs_for_blob_sidecars_requests)
+            .max(self.deneb_fork_epoch);
+
+        self.epoch_start_slot(retention_start_epoch)
+            .unwrap_or(u32::MAX)
+    }
+}
+
+#[cfg(test)]
+impl ChainSpec {
+    pub fn mainnet() -> Self {
+        Self {
+            seconds_per_slot: 12,
+            slots_per_epoch: 32,
+            genesis_time: 1606824023,
+            altair_fork_epoch: 74240,
+            bellatrix_fork_epoch: 144896,
+            capella_fork_epoch: 194048,
+            deneb_fork_epoch: 269568,
+            electra_fork_epoch: 364032,
+            fulu_fork_epoch: 411392,
+            min_epochs_for_blob_sidecars_requests: 4096,
+            max_blobs_per_block: 6,
+            max_blobs_per_block_electra: 9,
+            blob_schedule: vec![
+                BlobScheduleEntry {
+                    epoch: 412672,
+                    max_blobs_per_block: 15,
+                },
+                BlobScheduleEntry {
+                    epoch: 419072,
+                    max_blobs_per_block: 21,
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Client;
+    use serde_json::json;
+
+    use crate::{
+        clients::beacon::Config as BeaconClientConfig,
+        utils::mock_server::{start_server, Response},
+    };
+
+    use super::*;
+
+    fn create_beacon_client(base_url: String) -> BeaconClient {
+        BeaconClient::try_with_client(
+            Client::new(),
+            BeaconClientConfig {
+                base_urls: vec![base_url],
+                blob_sources: vec![],
+                exp_backoff: None,
+            },
+        )
+        .unwrap()
+    }
+
+    fn slot_of_epoch(epoch: u64) -> u32 {
+        ChainSpec::mainnet().epoch_start_slot(epoch).unwrap()
+    }
+
+    #[test]
+    fn test_new_from_spec() {
+        let spec: Spec = serde_json::from_str(
+            r#"{
+                "CONFIG_NAME": "mainnet",
+                "SECONDS_PER_SLOT": "12",
+                "SLOTS_PER_EPOCH": "32",
+                "ALTAIR_FORK_EPOCH": "74240",
+                "BELLATRIX_FORK_EPOCH": "144896",
+                "CAPELLA_FORK_EPOCH": "194048",
+                "DENEB_FORK_EPOCH": "269568",
+                "ELECTRA_FORK_EPOCH": "364032",
+                "FULU_FORK_EPOCH": "411392",
+                "MIN_EPOCHS_FOR_BLOB_SIDECARS_REQUESTS": "4096",
+                "MAX_BLOBS_PER_BLOCK": "6",
+                "MAX_BLOBS_PER_BLOCK_ELECTRA": "9",
+                "BLOB_SCHEDULE": [
+                    { "EPOCH": "412672", "MAX_BLOBS_PER_BLOCK": "15" },
+                    { "EPOCH": "419072", "MAX_BLOBS_PER_BLOCK": "21" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let chain_spec = ChainSpec::new(
+            spec,
+            Genesis {
+                genesis_time: 1606824023,
+            },
+        );
+        let mainnet = ChainSpec::mainnet();
+
+        assert_eq!(chain_spec.seconds_per_slot, mainnet.seconds_per_slot);
+        assert_eq!(chain_spec.slots_per_epoch, mainnet.slots_per_epoch);
+        assert_eq!(chain_spec.genesis_time, mainnet.genesis_time);
+        assert_eq!(chain_spec.deneb_fork_epoch, mainnet.deneb_fork_epoch);
+        assert_eq!(chain_spec.electra_fork_epoch, mainnet.electra_fork_epoch);
+        assert_eq!(chain_spec.fulu_fork_epoch, mainnet.fulu_fork_epoch);
+        assert_eq!(chain_spec.max_blobs_per_block, 6);
+        assert_eq!(chain_spec.max_blobs_per_block_electra, 9);
+        assert_eq!(chain_spec.blob_schedule.len(), 2);
+        assert_eq!(chain_spec.blob_schedule[1].epoch, 419072);
+        assert_eq!(chain_spec.blob_schedule[1].max_blobs_per_block, 21);
+    }
+
+    #[test]
+    fn test_new_from_spec_without_later_forks() {
+        let spec: Spec = serde_json::from_str(
+            r#"{
+                "SECONDS_PER_SLOT": "12",
+                "SLOTS_PER_EPOCH": "32",
+                "ALTAIR_FORK_EPOCH": "74240",
+                "BELLATRIX_FORK_EPOCH": "144896",
+                "CAPELLA_FORK_EPOCH": "194048",
+                "DENEB_FORK_EPOCH": "269568"
+            }"#,
+        )
+        .unwrap();
+        let chain_spec = ChainSpec::new(spec, Genesis { genesis_time: 0 });
+
+        assert_eq!(chain_spec.electra_fork_epoch, u64::MAX);
+        assert_eq!(chain_spec.fulu_fork_epoch, u64::MAX);
+        assert_eq!(chain_spec.min_epochs_for_blob_sidecars_requests, 4096);
+        assert_eq!(chain_spec.max_blobs_per_block, 6);
+        assert_eq!(chain_spec.max_blobs_per_block_electra, 9);
+        assert!(chain_spec.blob_schedule.is_empty());
+        assert_eq!(chain_spec.fork_at_slot(u32::MAX), Fork::Deneb);
+    }
+
+    #[test]
+    fn test_fork_at_slot() {
+        let chain_spec = ChainSpec::mainnet();
+
+        assert_eq!(chain_spec.fork_at_slot(0), Fork::Phase0);
+        assert_eq!(
+            chain_spec.fork_at_slot(slot_of_epoch(74240) - 1),
+            Fork::Phase0
+        );
+        assert_eq!(chain_spec.fork_at_slot(slot_of_epoch(74240)), Fork::Altair);
+        assert_eq!(
+            chain_spec.fork_at_slot(slot_of_epoch(144896)),
+            Fork::Bellatrix
+        );
+        assert_eq!(
+            chain_spec.fork_at_slot(slot_of_epoch(194048)),
+            Fork::Capella
+        );
+        assert_eq!(chain_spec.fork_at_slot(8626175), Fork::Capella);
+        assert_eq!(chain_spec.fork_at_slot(8626176), Fork::Deneb);
+        assert_eq!(
+            chain_spec.fork_at_slot(slot_of_epoch(364032)),
+            Fork::Electra
+        );
+        assert_eq!(chain_spec.fork_at_slot(slot_of_epoch(411392)), Fork::Fulu);
+    }
+
+    #[test]
+    fn test_timestamp_to_slot() {
+        let chain_spec = ChainSpec::mainnet();
+        let genesis_time = chain_spec.genesis_time;
+
+        assert_eq!(chain_spec.timestamp_to_slot(genesis_time), 0);
+        assert_eq!(chain_spec.timestamp_to_slot(genesis_time + 12), 1);
+        assert_eq!(
+            chain_spec.timestamp_to_slot(genesis_time + 8626176 * 12),
+            8626176
+        );
+    }
+
+    #[test]
+    fn test_timestamp_to_slot_within_slot() {
+        let chain_spec = ChainSpec::mainnet();
+        let genesis_time = chain_spec.genesis_time;
+
+        // Rounds up to the first slot starting at or after the timestamp
+        assert_eq!(chain_spec.timestamp_to_slot(genesis_time + 1), 1);
+        assert_eq!(chain_spec.timestamp_to_slot(genesis_time + 11), 1);
+        assert_eq!(chain_spec.timestamp_to_slot(genesis_time + 13), 2);
+    }
+
+    #[test]
+    fn test_timestamp_to_slot_before_genesis() {
+        let chain_spec = ChainSpec::mainnet();
+
+        assert_eq!(chain_spec.timestamp_to_slot(0), 0);
+        assert_eq!(chain_spec.timestamp_to_slot(chain_spec.genesis_time - 1), 0);
+    }
+
+    #[test]
+    fn test_timestamp_to_slot_beyond_slot_range() {
+        assert_eq!(ChainSpec::mainnet().timestamp_to_slot(u64::MAX), u32::MAX);
+    }
+
+    #[test]
+    fn test_deneb_fork_slot() {
+        let mut chain_spec = ChainSpec::mainnet();
+
+        assert_eq!(chain_spec.deneb_fork_slot(), Some(8626176));
+
+        chain_spec.deneb_fork_epoch = u64::MAX;
+
+        assert_eq!(chain_spec.deneb_fork_slot(), None);
+    }
+
+    #[test]
+    fn test_max_blobs_per_block() {
+        let chain_spec = ChainSpec::mainnet();
+        let fulu_fork_slot = slot_of_epoch(411392);
+
+        assert_eq!(chain_spec.max_blobs_per_block(Fork::Capella, 0), Some(0));
+        assert_eq!(
+            chain_spec.max_blobs_per_block(Fork::Deneb, 8626176),
+            Some(6)
+        );
+        assert_eq!(
+            chain_spec.max_blobs_per_block(Fork::Electra, slot_of_epoch(364032)),
+            Some(9)
+        );
+        assert_eq!(
+            chain_spec.max_blobs_per_block(Fork::Fulu, fulu_fork_slot),
+            Some(9)
+        );
+        assert_eq!(
+            chain_spec.max_blobs_per_block(Fork::Fulu, slot_of_epoch(412672) - 1),
+            Some(9)
+        );
+        assert_eq!(
+            chain_spec.max_blobs_per_block(Fork::Fulu, slot_of_epoch(412672)),
+            Some(15)
+        );
+        assert_eq!(
+            chain_spec.max_blobs_per_block(Fork::Fulu, slot_of_epoch(419072)),
+            Some(21)
+        );
+    }
+
+    #[test]
+    fn test_max_blobs_per_block_without_blob_schedule() {
+        let mut chain_spec = ChainSpec::mainnet();
+
+        chain_spec.blob_schedule.clear();
+
+        assert_eq!(
+            chain_spec.max_blobs_per_block(Fork::Fulu, slot_of_epoch(419072)),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch() {
+        let base_url = start_server(|request| match request.path.as_str() {
+            "/eth/v1/config/spec" => Response::json(json!({
+                "data": {
+                    "SECONDS_PER_SLOT": "12",
+                    "SLOTS_PER_EPOCH": "32",
+                    "ALTAIR_FORK_EPOCH": "50",
+                    "BELLATRIX_FORK_EPOCH": "100",
+                    "CAPELLA_FORK_EPOCH": "56832",
+                    "DENEB_FORK_EPOCH": "132608",
+                    "ELECTRA_FORK_EPOCH": "222464"
+                }
+            })),
+            "/eth/v1/beacon/genesis" => Response::json(json!({
+                "data": {
+                    "genesis_time": "1655733600",
+                    "genesis_validators_root": "0xd8ea171f3c94aea21ebc42a1ed61052acf3f9209c00e4efbaaddac09ed9b8078",
+                    "genesis_fork_version": "0x90000069"
+                }
+            })),
+            _ => Response::not_found(),
+        })
+        .await;
+        let chain_spec = ChainSpec::fetch(&create_beacon_client(base_url))
+            .await
+            .unwrap();
+
+        assert_eq!(chain_spec.genesis_time, 1655733600);
+        assert_eq!(chain_spec.deneb_fork_slot(), Some(4243456));
+        assert_eq!(chain_spec.fork_at_slot(7118848), Fork::Electra);
+        assert_eq!(chain_spec.fulu_fork_epoch, u64::MAX);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_without_spec() {
+        let base_url = start_server(|request| match request.path.as_str() {
+            "/eth/v1/beacon/genesis" => {
+                Response::json(json!({ "data": { "genesis_time": "1655733600" } }))
+            }
+            _ => Response::not_found(),
+        })
+        .await;
+
+        assert!(ChainSpec::fetch(&create_beacon_client(base_url))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_epoch_start_slot() {
+        let chain_spec = ChainSpec::mainnet();
+
+        assert_eq!(chain_spec.epoch_start_slot(0), Some(0));
+        assert_eq!(chain_spec.epoch_start_slot(269568), Some(8626176));
+        // Slots are stored as `u32`, which far future epochs don't fit in
+        assert_eq!(chain_spec.epoch_start_slot(u64::MAX), None);
+        assert_eq!(chain_spec.epoch_start_slot(u32::MAX as u64), None);
+    }
+
+    #[test]
+    fn test_current_slot() {
+        let mut chain_spec = ChainSpec::mainnet();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        chain_spec.genesis_time = now - 100 * 12 - 6;
+
+        // A second may have passed since computing the genesis time
+        assert!((100..=101).contains(&chain_spec.current_slot()));
+
+        chain_spec.genesis_time = now + 3600;
+
+        assert_eq!(chain_spec.current_slot(), 0);
+    }
+
+    /// Mainnet spec whose wall clock is in the middle of the given epoch.
+    fn chain_spec_at_epoch(epoch: u64) -> ChainSpec {
+        let mut chain_spec = ChainSpec::mainnet();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        chain_spec.genesis_time = now - epoch * 32 * 12 - 16 * 12;
+
+        chain_spec
+    }
+
+    #[test]
+    fn test_blobs_retention_start_slot() {
+        let chain_spec = chain_spec_at_epoch(300000);
+
+        assert_eq!(
+            chain_spec.blobs_retention_start_slot(),
+            slot_of_epoch(300000 - 4096)
+        );
+    }
+
+    #[test]
+    fn test_blobs_retention_start_slot_within_retention_period_of_deneb() {
+        // Nodes serve every blob since Deneb until the retention period has passed
+        let chain_spec = chain_spec_at_epoch(269568 + 4095);
+
+        assert_eq!(chain_spec.blobs_retention_start_slot(), 8626176);
+
+        let chain_spec = chain_spec_at_epoch(100);
+
+        assert_eq!(chain_spec.blobs_retention_start_slot(), 8626176);
+    }
+
+    #[test]
+    fn test_blobs_retention_start_slot_without_deneb() {
+        let mut chain_spec = chain_spec_at_epoch(300000);
+
+        chain_spec.deneb_fork_epoch = u64::MAX;
+
+        assert_eq!(chain_spec.blobs_retention_start_slot(), u32::MAX);
+    }
+}
@@ -0,0 +1,223 @@
+use std::{fmt::Debug, io::ErrorKind, path::PathBuf};
+
+use anyhow::{anyhow, Context as AnyhowContext};
+use async_trait::async_trait;
+use backoff::ExponentialBackoff;
+use ethers::types::H256;
+use reqwest::{Client, Url};
+
+use crate::{clients::common::ClientResult, json_get};
+
+use super::ssz::decode_blob_sidecars;
+use super::types::{Blob, BlobsResponse};
+
+/// Secondary source of blob sidecars, queried when the beacon nodes don't have them anymore.
+#[async_trait]
+pub trait BlobSource: Debug + Send + Sync {
+    fn name(&self) -> String;
+
+    async fn get_blobs(&self, block_root: H256) -> ClientResult<Option<Vec<Blob>>>;
+}
+
+/// Archival node exposing the beacon API blob sidecars endpoint.
+#[derive(Debug)]
+pub struct BeaconApiBlobSource {
+    base_url: Url,
+    client: Client,
+    exp_backoff: Option<ExponentialBackoff>,
+}
+
+impl BeaconApiBlobSource {
+    pub fn try_with_client(
+        client: Client,
+        base_url: &str,
+        exp_backoff: Option<ExponentialBackoff>,
+    ) -> ClientResult<Self> {
+        let base_url = Url::parse(&format!("{base_url}/eth/"))
+            .with_context(|| format!("Failed to parse blob archive URL {base_url}"))?;
+
+        Ok(Self {
+            base_url,
+            client,
+            exp_backoff,
+        })
+    }
+}
+
+#[async_trait]
+impl BlobSource for BeaconApiBlobSource {
+    fn name(&self) -> String {
+        self.base_url.to_string()
+    }
+
+    async fn get_blobs(&self, block_root: H256) -> ClientResult<Option<Vec<Blob>>> {
+        let url = self
+            .base_url
+            .join(&format!("v1/beacon/blob_sidecars/{block_root:#x}"))?;
+
+        json_get!(&self.client, url, BlobsResponse, self.exp_backoff.clone()).map(|res| match res {
+            Some(r) => Some(r.data),
+            None => None,
+        })
+    }
+}
+
+/// Directory of blob sidecar files named after the root of their block, either SSZ-encoded
+/// (`<root>.ssz`) or as returned by the beacon API (`<root>.json`).
+#[derive(Debug)]
+pub struct DirectoryBlobSource {
+    path: PathBuf,
+}
+
+impl DirectoryBlobSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    async fn _read_file(&self, file_name: String) -> ClientResult<Option<Vec<u8>>> {
+        let file_path = self.path.join(file_name);
+
+        match tokio::fs::read(&file_path).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(anyhow!(error)
+                .context(format!("Failed to read {}", file_path.display()))
+                .into()),
+        }
+    }
+}
+
+#[async_trait]
+impl BlobSource for DirectoryBlobSource {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    async fn get_blobs(&self, block_root: H256) -> ClientResult<Option<Vec<Blob>>> {
+        if let Some(bytes) = self._read_file(format!("{block_root:#x}.ssz")).await? {
+            return Ok(Some(decode_blob_sidecars(&bytes)?));
+        }
+
+        match self._read_file(format!("{block_root:#x}.json")).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice::<BlobsResponse>(&bytes)?.data)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Root of the block of the sidecar fixtures.
+    const BLOCK_ROOT: &str = "0xc7d9a15af18c4e4db0f69d1e3d6b6f2b8b1ffea2291f65bb4134bbc4dece8782";
+    const SSZ_BLOB_SIDECARS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blob_sidecars.ssz"
+    ));
+    const JSON_BLOB_SIDECARS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blob_sidecar.json"
+    ));
+
+    fn create_directory(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "blob-indexer-{}-{name}-blob-source",
+            std::process::id()
+        ));
+
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+
+        for (file_name, content) in files {
+            std::fs::write(path.join(file_name), content).unwrap();
+        }
+
+        path
+    }
+
+    fn block_root() -> H256 {
+        BLOCK_ROOT.parse().unwrap()
+    }
+
+    /// Both fixtures hold the sidecar at index 1, the SSZ one preceded by the one at index 0.
+    fn assert_blobs(blobs: &[Blob], expected_len: usize) {
+        let blob = blobs.last().unwrap();
+
+        assert_eq!(blobs.len(), expected_len);
+        assert_eq!(blob.index, "1");
+        assert_eq!(blob.blob.len(), 131072);
+        assert_eq!(blob.signed_block_header.message.slot, 1409759);
+    }
+
+    #[tokio::test]
+    async fn test_get_ssz_blobs() {
+        let path = create_directory("ssz", &[(&format!("{BLOCK_ROOT}.ssz"), SSZ_BLOB_SIDECARS)]);
+        let blob_source = DirectoryBlobSource::new(&path);
+
+        assert_blobs(
+            &blob_source.get_blobs(block_root()).await.unwrap().unwrap(),
+            2,
+        );
+
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_json_blobs() {
+        let path = create_directory(
+            "json",
+            &[(&format!("{BLOCK_ROOT}.json"), JSON_BLOB_SIDECARS)],
+        );
+        let blob_source = DirectoryBlobSource::new(&path);
+
+        assert_blobs(
+            &blob_source.get_blobs(block_root()).await.unwrap().unwrap(),
+            1,
+        );
+
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_prefer_ssz_blobs() {
+        let path = create_directory(
+            "both",
+            &[
+                (&format!("{BLOCK_ROOT}.ssz"), SSZ_BLOB_SIDECARS),
+                (&format!("{BLOCK_ROOT}.json"), JSON_BLOB_SIDECARS),
+            ],
+        );
+        let blob_source = DirectoryBlobSource::new(&path);
+
+        assert_blobs(
+            &blob_source.get_blobs(block_root()).await.unwrap().unwrap(),
+            2,
+        );
+
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_missing_blobs() {
+        let path = create_directory("missing", &[]);
+        let blob_source = DirectoryBlobSource::new(&path);
+
+        assert!(blob_source.get_blobs(block_root()).await.unwrap().is_none());
+
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_invalid_blobs() {
+        let path = create_directory(
+            "invalid",
+            &[(&format!("{BLOCK_ROOT}.ssz"), &SSZ_BLOB_SIDECARS[1..])],
+        );
+        let blob_source = DirectoryBlobSource::new(&path);
+
+        assert!(blob_source.get_blobs(block_root()).await.is_err());
+
+        std::fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -0,0 +1,385 @@
+//! Reading of consensus layer Era archives. An Era file is an e2store file holding the blocks of
+//! `SLOTS_PER_HISTORICAL_ROOT` slots followed by the state at the end of them, both
+//! snappy-compressed, plus an index of each.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, bail, Context as AnyhowContext, Result};
+use ethers::types::H256;
+use snap::read::FrameDecoder;
+
+use crate::chain_spec::ChainSpec;
+
+use super::ssz::decode_signed_beacon_block;
+use super::types::Block;
+
+const SLOTS_PER_HISTORICAL_ROOT: u32 = 8192;
+
+const E2STORE_HEADER_SIZE: u64 = 8;
+const COMPRESSED_SIGNED_BEACON_BLOCK: [u8; 2] = [0x01, 0x00];
+const COMPRESSED_BEACON_STATE: [u8; 2] = [0x02, 0x00];
+const SLOT_INDEX: [u8; 2] = [0x69, 0x32];
+
+/// Size of a slot index record: header, starting slot, one offset per slot and count.
+const BLOCK_INDEX_SIZE: u64 = E2STORE_HEADER_SIZE + 8 + SLOTS_PER_HISTORICAL_ROOT as u64 * 8 + 8;
+const STATE_INDEX_SIZE: u64 = E2STORE_HEADER_SIZE + 8 + 8 + 8;
+
+/// Position of `block_roots` within the fixed part of a `BeaconState`, the same on every fork.
+const STATE_BLOCK_ROOTS_POSITION: usize = 176;
+
+/// Maximum amount of Era indexes kept in memory.
+const MAX_CACHED_ERAS: usize = 16;
+
+/// Location of the blocks of an Era file along with their roots, which are read from the
+/// `block_roots` of the state closing the era.
+#[derive(Debug)]
+struct EraIndex {
+    path: PathBuf,
+    block_positions: Vec<Option<u64>>,
+    block_roots: Vec<H256>,
+}
+
+/// Directory of Era files blocks can be read from instead of fetching them from a beacon node.
+#[derive(Debug)]
+pub struct EraStore {
+    files: BTreeMap<u32, PathBuf>,
+    chain_spec: ChainSpec,
+    indexes: Mutex<HashMap<u32, Arc<EraIndex>>>,
+}
+
+impl EraStore {
+    /// Lists the Era files of the directory, which are named
+    /// `<network>-<era number>-<short historical root>.era`.
+    pub fn try_new(dir: impl AsRef<Path>, chain_spec: ChainSpec) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut files = BTreeMap::new();
+
+        for entry in fs::read_dir(dir)
+            .with_context(|| format!("Failed to read Era files directory {}", dir.display()))?
+        {
+            let path = entry?.path();
+
+            if path.extension().is_none_or(|extension| extension != "era") {
+                continue;
+            }
+
+            let era = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.rsplit('-').nth(1))
+                .and_then(|era| era.parse::<u32>().ok())
+                .with_context(|| format!("Invalid Era file name {}", path.display()))?;
+
+            files.insert(era, path);
+        }
+
+        Ok(Self {
+            files,
+            chain_spec,
+            indexes: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Whether the Era file containing the given slot is available.
+    pub fn contains_slot(&self, slot: u32) -> bool {
+        self.files.contains_key(&Self::era_of_slot(slot))
+    }
+
+    /// Reads the block of the given slot along with its root. Returns `None` if the slot is
+    /// empty.
+    pub async fn get_block(&self, slot: u32) -> Result<Option<(Block, H256)>> {
+        let fork = self.chain_spec.fork_at_slot(slot);
+        let index = self._get_era_index(Self::era_of_slot(slot)).await?;
+        let slot_index = (slot % SLOTS_PER_HISTORICAL_ROOT) as usize;
+
+        let block_position = match index.block_positions[slot_index] {
+            Some(position) => position,
+            None => return Ok(None),
+        };
+        let block_root = index.block_roots[slot_index];
+        let path = index.path.clone();
+
+        let block = tokio::task::spawn_blocking(move || -> Result<Block> {
+            let mut file = File::open(&path)?;
+            let bytes = read_compressed_record(
+                &mut file,
+                block_position,
+                COMPRESSED_SIGNED_BEACON_BLOCK,
+                None,
+            )?;
+
+            decode_signed_beacon_block(&bytes, fork)
+        })
+        .await??;
+
+        if block.message.slot != slot {
+            bail!(
+                "Era file block at slot {} found in place of slot {slot}",
+                block.message.slot
+            );
+        }
+
+        Ok(Some((block, block_root)))
+    }
+
+    fn era_of_slot(slot: u32) -> u32 {
+        slot / SLOTS_PER_HISTORICAL_ROOT + 1
+    }
+
+    async fn _get_era_index(&self, era: u32) -> Result<Arc<EraIndex>> {
+        if let Some(index) = self.indexes.lock().unwrap().get(&era) {
+            return Ok(index.clone());
+        }
+
+        let path = self
+            .files
+            .get(&era)
+            .with_context(|| format!("Era file {era} not found"))?
+            .clone();
+        let index = Arc::new(tokio::task::spawn_blocking(move || read_era_index(path)).await??);
+        let mut indexes = self.indexes.lock().unwrap();
+
+        if indexes.len() >= MAX_CACHED_ERAS {
+            indexes.clear();
+        }
+
+        indexes.insert(era, index.clone());
+
+        Ok(index)
+    }
+}
+
+fn read_era_index(path: PathBuf) -> Result<EraIndex> {
+    let mut file =
+        File::open(&path).with_context(|| format!("Failed to open Era file {}", path.display()))?;
+    let file_size = file.metadata()?.len();
+
+    // The state index is the last record, preceded by the block index
+    let state_index_position = file_size
+        .checked_sub(STATE_INDEX_SIZE)
+        .context("Era file too small")?;
+    let state_index = read_record(&mut file, state_index_position, SLOT_INDEX)?;
+    let state_position = offset_position(state_index_position, read_i64(&state_index, 8)?)?;
+
+    let block_index_position = state_index_position
+        .checked_sub(BLOCK_INDEX_SIZE)
+        .context("Era file has no block index")?;
+    let block_index = read_record(&mut file, block_index_position, SLOT_INDEX)?;
+    let block_positions = (0..SLOTS_PER_HISTORICAL_ROOT as usize)
+        .map(|slot_index| {
+            let offset = read_i64(&block_index, 8 + slot_index * 8)?;
+
+            block_position(block_index_position, offset)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let state = read_compressed_record(
+        &mut file,
+        state_position,
+        COMPRESSED_BEACON_STATE,
+        Some(STATE_BLOCK_ROOTS_POSITION + SLOTS_PER_HISTORICAL_ROOT as usize * 32),
+    )?;
+    let block_roots = state[STATE_BLOCK_ROOTS_POSITION..]
+        .chunks_exact(32)
+        .map(H256::from_slice)
+        .collect();
+
+    Ok(EraIndex {
+        path,
+        block_positions,
+        block_roots,
+    })
+}
+
+fn read_record(file: &mut File, position: u64, record_type: [u8; 2]) -> Result<Vec<u8>> {
+    let length = seek_record(file, position, record_type)?;
+    let mut data = vec![0; length as usize];
+
+    file.read_exact(&mut data)?;
+
+    Ok(data)
+}
+
+/// Reads and decompresses a snappy-framed record. Only the first `max_size` bytes are
+/// decompressed if given.
+fn read_compressed_record(
+    file: &mut File,
+    position: u64,
+    record_type: [u8; 2],
+    max_size: Option<usize>,
+) -> Result<Vec<u8>> {
+    let length = seek_record(file, position, record_type)?;
+    let mut decoder = FrameDecoder::new(file.take(length));
+    let mut data = vec![];
+
+    match max_size {
+        Some(max_size) => {
+            data.resize(max_size, 0);
+            decoder.read_exact(&mut data)?;
+        }
+        None => {
+            decoder.read_to_end(&mut data)?;
+        }
+    }
+
+    Ok(data)
+}
+
+/// Moves to the data of the record at the given position and returns its length.
+fn seek_record(file: &mut File, position: u64, record_type: [u8; 2]) -> Result<u64> {
+    let mut header = [0; E2STORE_HEADER_SIZE as usize];
+
+    file.seek(SeekFrom::Start(position))?;
+    file.read_exact(&mut header)?;
+
+    if header[0..2] != record_type {
+        bail!(
+            "Unexpected e2store record type {} at position {position}",
+            hex::encode(&header[0..2])
+        );
+    }
+
+    Ok(u32::from_le_bytes(header[2..6].try_into()?) as u64)
+}
+
+fn read_i64(bytes: &[u8], position: usize) -> Result<i64> {
+    let value = bytes
+        .get(position..position + 8)
+        .with_context(|| format!("Position {position} out of bounds"))?;
+
+    Ok(i64::from_le_bytes(value.try_into()?))
+}
+
+/// Position of the block a slot index offset points to. Empty slots have no block, and their
+/// offset points to the start of the file, where the version record is, or is zero.
+fn block_position(index_position: u64, offset: i64) -> Result<Option<u64>> {
+    if offset == 0 {
+        return Ok(None);
+    }
+
+    match offset_position(index_position, offset)? {
+        0 => Ok(None),
+        position => Ok(Some(position)),
+    }
+}
+
+/// Slot index offsets are relative to the start of the index record.
+fn offset_position(index_position: u64, offset: i64) -> Result<u64> {
+    index_position
+        .checked_add_signed(offset)
+        .ok_or_else(|| anyhow!("Invalid slot index offset {offset}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::types::{BlockBody, Fork};
+    use super::*;
+
+    const ERA_FILES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/era");
+    const ERA_FILE: &str = "mainnet-01054-c02e6bc3.era";
+    /// First slot of the era, which is the Deneb fork slot on mainnet.
+    const ERA_START_SLOT: u32 = 8626176;
+    /// Positions of the blocks of the era file, the only non-empty slots being the 1st, 2nd
+    /// and 6th.
+    const BLOCK_POSITIONS: [(usize, u64); 3] = [(0, 8), (1, 1691), (5, 3402)];
+    const BLOCK_ROOTS: [&str; 3] = [
+        "0xc868971a14c7ef6d38c462982eae5302c6e1f62d968dae7a3260ceae68658b9a",
+        "0x26b4fef277a4078fc88cad9b1f69784dc74e65d01522553aefb46110a69d44f6",
+        "0xad1cbcbee458af87233b0470e466ae395ea809837aa94e7323d059e25aa4c540",
+    ];
+
+    fn block_root(index: usize) -> H256 {
+        BLOCK_ROOTS[index].parse().unwrap()
+    }
+
+    #[test]
+    fn test_state_block_roots_position() {
+        // genesis_time, genesis_validators_root, slot, fork and latest_block_header
+        assert_eq!(STATE_BLOCK_ROOTS_POSITION, 8 + 32 + 8 + 16 + 112);
+    }
+
+    #[test]
+    fn test_read_era_index() {
+        let index = read_era_index(Path::new(ERA_FILES_DIR).join(ERA_FILE)).unwrap();
+
+        assert_eq!(
+            index.block_positions.len(),
+            SLOTS_PER_HISTORICAL_ROOT as usize
+        );
+        assert_eq!(index.block_roots.len(), SLOTS_PER_HISTORICAL_ROOT as usize);
+
+        for (slot_index, block_position) in index.block_positions.iter().enumerate() {
+            let expected_position = BLOCK_POSITIONS
+                .iter()
+                .find(|(block_slot_index, _)| *block_slot_index == slot_index)
+                .map(|(_, position)| *position);
+
+            assert_eq!(
+                *block_position, expected_position,
+                "slot index {slot_index}"
+            );
+        }
+
+        // Empty slots keep the root of the latest block before them
+        assert_eq!(index.block_roots[0], block_root(0));
+        assert_eq!(index.block_roots[1], block_root(1));
+        assert_eq!(index.block_roots[4], block_root(1));
+        assert_eq!(index.block_roots[5], block_root(2));
+        assert_eq!(index.block_roots[8191], block_root(2));
+    }
+
+    #[test]
+    fn test_block_position() {
+        assert_eq!(block_position(1000, -992).unwrap(), Some(8));
+        assert_eq!(block_position(1000, -1000).unwrap(), None);
+        assert_eq!(block_position(1000, 0).unwrap(), None);
+        assert!(block_position(1000, -1001).is_err());
+    }
+
+    #[test]
+    fn test_era_store_contains_slot() {
+        let era_store = EraStore::try_new(ERA_FILES_DIR, ChainSpec::mainnet()).unwrap();
+
+        assert!(era_store.contains_slot(ERA_START_SLOT));
+        assert!(era_store.contains_slot(ERA_START_SLOT + SLOTS_PER_HISTORICAL_ROOT - 1));
+        assert!(!era_store.contains_slot(ERA_START_SLOT - 1));
+        assert!(!era_store.contains_slot(ERA_START_SLOT + SLOTS_PER_HISTORICAL_ROOT));
+    }
+
+    #[tokio::test]
+    async fn test_era_store_get_block() {
+        let era_store = EraStore::try_new(ERA_FILES_DIR, ChainSpec::mainnet()).unwrap();
+
+        let (block, root) = era_store
+            .get_block(ERA_START_SLOT + 5)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(root, block_root(2));
+        assert_eq!(block.fork, Fork::Deneb);
+        assert_eq!(block.message.slot, ERA_START_SLOT + 5);
+        assert_eq!(block.message.parent_root, block_root(1));
+        assert!(matches!(
+            block.message.body,
+            BlockBody::Deneb { ref blob_kzg_commitments, .. } if blob_kzg_commitments.len() == 6
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_era_store_get_block_of_empty_slot() {
+        let era_store = EraStore::try_new(ERA_FILES_DIR, ChainSpec::mainnet()).unwrap();
+
+        assert!(era_store
+            .get_block(ERA_START_SLOT + 2)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,87 +1,907 @@
-use anyhow::Context as AnyhowContext;
+use std::{future::Future, sync::Arc};
+
+use anyhow::{anyhow, Context as AnyhowContext};
 use backoff::ExponentialBackoff;
-use reqwest::{Client, Url};
+use futures::future::join_all;
+use reqwest::{
+    header::{ACCEPT, CONTENT_TYPE},
+    Client, StatusCode, Url,
+};
 use reqwest_eventsource::EventSource;
+use tracing::{debug, info, warn};
 
 use crate::{
     clients::{beacon::types::BlockHeaderResponse, common::ClientResult},
     json_get,
 };
 
-use self::types::{Blob, BlobsResponse, Block, BlockHeader, BlockId, BlockResponse, Topic};
+use self::blob_source::BlobSource;
+use self::node_pool::NodePool;
+use self::ssz::{decode_blob_sidecars, decode_signed_beacon_block};
+use self::types::{
+    Blob, BlobsResponse, Block, BlockHeader, BlockId, BlockResponse, Fork, Genesis,
+    GenesisResponse, Spec, SpecResponse, SyncStatus, SyncStatusResponse, Topic,
+};
 
+pub mod blob_source;
+pub mod era;
+mod node_pool;
+mod ssz;
 pub mod types;
 
+const SSZ_CONTENT_TYPE: &str = "application/octet-stream";
+const CONSENSUS_VERSION_HEADER: &str = "Eth-Consensus-Version";
+
+/// Client for a pool of beacon nodes. Requests are routed to the healthiest node and fail over
+/// to the next one when it errors out.
 #[derive(Debug, Clone)]
 pub struct BeaconClient {
-    base_url: Url,
+    node_pool: Arc<NodePool>,
+    blob_sources: Arc<Vec<Box<dyn BlobSource>>>,
     client: Client,
     exp_backoff: Option<ExponentialBackoff>,
 }
 
+enum SszResponse {
+    Found {
+        bytes: Vec<u8>,
+        consensus_version: String,
+    },
+    NotFound,
+}
+
 pub struct Config {
-    pub base_url: String,
+    pub base_urls: Vec<String>,
+    /// Sources blobs are looked up in, in order, when the beacon nodes don't have them.
+    pub blob_sources: Vec<Box<dyn BlobSource>>,
     pub exp_backoff: Option<ExponentialBackoff>,
 }
 
 impl BeaconClient {
     pub fn try_with_client(client: Client, config: Config) -> ClientResult<Self> {
-        let base_url = Url::parse(&format!("{}/eth/", config.base_url))
-            .with_context(|| "Failed to parse base URL")?;
+        if config.base_urls.is_empty() {
+            return Err(anyhow!("No beacon node base URL provided").into());
+        }
+
+        let base_urls = config
+            .base_urls
+            .iter()
+            .map(|base_url| {
+                Url::parse(&format!("{base_url}/eth/"))
+                    .with_context(|| format!("Failed to parse base URL {base_url}"))
+            })
+            .collect::<Result<Vec<Url>, _>>()?;
         let exp_backoff = config.exp_backoff;
 
         Ok(Self {
-            base_url,
+            node_pool: Arc::new(NodePool::new(base_urls)),
+            blob_sources: Arc::new(config.blob_sources),
             client,
             exp_backoff,
         })
     }
 
+    /// Whether blobs pruned by the beacon nodes can still be looked up somewhere else.
+    pub fn has_blob_sources(&self) -> bool {
+        !self.blob_sources.is_empty()
+    }
+
     pub async fn get_block(&self, block_id: &BlockId) -> ClientResult<Option<Block>> {
-        let path = format!("v2/beacon/blocks/{block_id}");
-        let url = self.base_url.join(path.as_str())?;
+        let path = &format!("v2/beacon/blocks/{block_id}");
+
+        self._request(|node, exp_backoff| async move {
+            let url = self.node_pool.base_url(node).join(path.as_str())?;
 
-        json_get!(&self.client, url, BlockResponse, self.exp_backoff.clone()).map(|res| match res {
-            Some(r) => Some(r.data),
-            None => None,
+            if let Some(response) = self._get_ssz(node, &url, exp_backoff.clone()).await? {
+                let block = match response {
+                    SszResponse::NotFound => return Ok(None),
+                    SszResponse::Found {
+                        bytes,
+                        consensus_version,
+                    } => consensus_version
+                        .parse::<Fork>()
+                        .map_err(|error| anyhow!(error))
+                        .and_then(|fork| decode_signed_beacon_block(&bytes, fork)),
+                };
+
+                match block {
+                    Ok(block) => return Ok(Some(block)),
+                    Err(error) => debug!(
+                        target = "beacon_client",
+                        url = %url,
+                        %error,
+                        "Failed to decode SSZ block. Falling back to JSON…"
+                    ),
+                }
+            }
+
+            match json_get!(&self.client, url, BlockResponse, exp_backoff)? {
+                Some(response) => Ok(Some(Block::try_from(response)?)),
+                None => Ok(None),
+            }
         })
+        .await
     }
 
     pub async fn get_block_header(&self, block_id: &BlockId) -> ClientResult<Option<BlockHeader>> {
-        let path = format!("v1/beacon/headers/{block_id}");
-        let url = self.base_url.join(path.as_str())?;
-
-        json_get!(
-            &self.client,
-            url,
-            BlockHeaderResponse,
-            self.exp_backoff.clone()
-        )
-        .map(|res| match res {
-            Some(r) => Some(r.data),
-            None => None,
+        let path = &format!("v1/beacon/headers/{block_id}");
+
+        self._request(|node, exp_backoff| async move {
+            let url = self.node_pool.base_url(node).join(path.as_str())?;
+
+            json_get!(&self.client, url, BlockHeaderResponse, exp_backoff).map(|res| match res {
+                Some(r) => Some(r.data),
+                None => None,
+            })
         })
+        .await
     }
 
+    /// Fetches the blob sidecars of the block from the beacon nodes, falling back to the
+    /// configured blob sources when the nodes don't have them, as happens once pruned.
     pub async fn get_blobs(&self, block_id: &BlockId) -> ClientResult<Option<Vec<Blob>>> {
-        let path = format!("v1/beacon/blob_sidecars/{block_id}");
-        let url = self.base_url.join(path.as_str())?;
+        let blobs = self._get_blobs_from_nodes(block_id).await?;
+
+        if blobs.as_ref().is_some_and(|blobs| !blobs.is_empty()) {
+            return Ok(blobs);
+        }
+
+        // Sources are keyed by block root
+        let block_root = match block_id {
+            BlockId::Root(root) => *root,
+            _ => return Ok(blobs),
+        };
+
+        for blob_source in self.blob_sources.iter() {
+            match blob_source.get_blobs(block_root).await {
+                Ok(Some(source_blobs)) if !source_blobs.is_empty() => {
+                    debug!(
+                        target = "beacon_client",
+                        source = blob_source.name(),
+                        ?block_root,
+                        "Blobs fetched from fallback blob source"
+                    );
+
+                    return Ok(Some(source_blobs));
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    warn!(
+                        target = "beacon_client",
+                        source = blob_source.name(),
+                        ?block_root,
+                        ?error,
+                        "Failed to fetch blobs from fallback blob source"
+                    );
+                }
+            }
+        }
+
+        Ok(blobs)
+    }
 
-        json_get!(&self.client, url, BlobsResponse, self.exp_backoff.clone()).map(|res| match res {
-            Some(r) => Some(r.data),
-            None => None,
+    async fn _get_blobs_from_nodes(&self, block_id: &BlockId) -> ClientResult<Option<Vec<Blob>>> {
+        let path = &format!("v1/beacon/blob_sidecars/{block_id}");
+
+        self._request(|node, exp_backoff| async move {
+            let url = self.node_pool.base_url(node).join(path.as_str())?;
+
+            if let Some(response) = self._get_ssz(node, &url, exp_backoff.clone()).await? {
+                let blobs = match response {
+                    SszResponse::NotFound => return Ok(None),
+                    SszResponse::Found { bytes, .. } => decode_blob_sidecars(&bytes),
+                };
+
+                match blobs {
+                    Ok(blobs) => return Ok(Some(blobs)),
+                    Err(error) => debug!(
+                        target = "beacon_client",
+                        url = %url,
+                        %error,
+                        "Failed to decode SSZ blob sidecars. Falling back to JSON…"
+                    ),
+                }
+            }
+
+            json_get!(&self.client, url, BlobsResponse, exp_backoff).map(|res| match res {
+                Some(r) => Some(r.data),
+                None => None,
+            })
         })
+        .await
     }
 
+    pub async fn get_sync_status(&self) -> ClientResult<Option<SyncStatus>> {
+        self._request(|node, exp_backoff| async move {
+            let url = self.node_pool.base_url(node).join("v1/node/syncing")?;
+
+            json_get!(&self.client, url, SyncStatusResponse, exp_backoff).map(|res| match res {
+                Some(r) => Some(r.data),
+                None => None,
+            })
+        })
+        .await
+    }
+
+    pub async fn get_spec(&self) -> ClientResult<Option<Spec>> {
+        self._request(|node, exp_backoff| async move {
+            let url = self.node_pool.base_url(node).join("v1/config/spec")?;
+
+            json_get!(&self.client, url, SpecResponse, exp_backoff).map(|res| match res {
+                Some(r) => Some(r.data),
+                None => None,
+            })
+        })
+        .await
+    }
+
+    pub async fn get_genesis(&self) -> ClientResult<Option<Genesis>> {
+        self._request(|node, exp_backoff| async move {
+            let url = self.node_pool.base_url(node).join("v1/beacon/genesis")?;
+
+            json_get!(&self.client, url, GenesisResponse, exp_backoff).map(|res| match res {
+                Some(r) => Some(r.data),
+                None => None,
+            })
+        })
+        .await
+    }
+
+    /// Subscribes to the given topics on the healthiest node.
     pub fn subscribe_to_events(&self, topics: Vec<Topic>) -> ClientResult<EventSource> {
         let topics = topics
             .iter()
             .map(|topic| topic.into())
             .collect::<Vec<String>>()
-            .join("&");
+            .join(",");
         let path = format!("v1/events?topics={topics}");
-        let url = self.base_url.join(&path)?;
+        let node = self.node_pool.ranked_nodes()[0];
+        let url = self.node_pool.base_url(node).join(&path)?;
+
+        debug!(
+            target = "beacon_client",
+            node = %self.node_pool.base_url(node),
+            "Subscribing to beacon node events"
+        );
+
+        self.node_pool.set_events_node(node);
 
         Ok(EventSource::get(url))
     }
+
+    /// Whether the events subscription is opened on the node requests are routed to first.
+    pub fn is_subscribed_to_best_node(&self) -> bool {
+        self.node_pool
+            .events_node()
+            .is_none_or(|events_node| events_node == self.node_pool.ranked_nodes()[0])
+    }
+
+    /// Fetches the head of every node to keep track of which ones are reachable and synced.
+    pub async fn check_nodes_health(&self) {
+        let checks = (0..self.node_pool.len()).map(|node| async move {
+            let base_url = self.node_pool.base_url(node).clone();
+            let result: ClientResult<Option<BlockHeaderResponse>> = async {
+                let url = base_url.join("v1/beacon/headers/head")?;
+
+                json_get!(
+                    &self.client,
+                    url,
+                    BlockHeaderResponse,
+                    None::<ExponentialBackoff>
+                )
+            }
+            .await;
+
+            match result {
+                Ok(Some(response)) => {
+                    self.node_pool.report_success(node);
+                    self.node_pool
+                        .report_head_slot(node, response.data.header.message.slot);
+                }
+                Ok(None) => self.node_pool.report_failure(node),
+                Err(error) => {
+                    warn!(
+                        target = "beacon_client",
+                        node = %base_url,
+                        ?error,
+                        "Beacon node health check failed"
+                    );
+
+                    self.node_pool.report_failure(node);
+                }
+            }
+        });
+
+        join_all(checks).await;
+
+        for node in 0..self.node_pool.len() {
+            if !self.node_pool.is_node_healthy(node) {
+                warn!(
+                    target = "beacon_client",
+                    node = %self.node_pool.base_url(node),
+                    "Beacon node is unhealthy"
+                );
+            }
+        }
+    }
+
+    /// Requests the SSZ encoding of the resource. Returns `None` when the node doesn't support
+    /// it, which is remembered so JSON is requested straight away from then on.
+    async fn _get_ssz(
+        &self,
+        node: usize,
+        url: &Url,
+        exp_backoff: Option<ExponentialBackoff>,
+    ) -> ClientResult<Option<SszResponse>> {
+        if !self.node_pool.is_ssz_supported(node) {
+            return Ok(None);
+        }
+
+        debug!(
+            method = "GET",
+            url = url.as_str(),
+            "Dispatching SSZ API request"
+        );
+
+        let req = self
+            .client
+            .get(url.clone())
+            .header(ACCEPT, SSZ_CONTENT_TYPE);
+
+        let resp = match exp_backoff {
+            Some(exp_backoff) => {
+                backoff::future::retry_notify(
+                    exp_backoff,
+                    || {
+                        let req = req.try_clone().unwrap();
+
+                        async move { req.send().await.map_err(backoff::Error::transient) }
+                    },
+                    |error, duration: std::time::Duration| {
+                        let duration = duration.as_secs();
+
+                        warn!(
+                            method = "GET",
+                            url = %url,
+                            ?error,
+                            "Failed to send request. Retrying in {duration} seconds…"
+                        );
+                    },
+                )
+                .await?
+            }
+            None => req.send().await?,
+        };
+
+        let status = resp.status();
+
+        if status == StatusCode::NOT_FOUND {
+            return Ok(Some(SszResponse::NotFound));
+        }
+
+        let is_ssz = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with(SSZ_CONTENT_TYPE));
+
+        if status == StatusCode::NOT_ACCEPTABLE
+            || status == StatusCode::UNSUPPORTED_MEDIA_TYPE
+            || (status.is_success() && !is_ssz)
+        {
+            info!(
+                target = "beacon_client",
+                node = %self.node_pool.base_url(node),
+                "Beacon node doesn't support SSZ responses. Falling back to JSON…"
+            );
+
+            self.node_pool.set_ssz_unsupported(node);
+
+            return Ok(None);
+        }
+
+        let resp = resp.error_for_status()?;
+        let consensus_version = resp
+            .headers()
+            .get(CONSENSUS_VERSION_HEADER)
+            .and_then(|version| version.to_str().ok())
+            .unwrap_or_default()
+            .to_lowercase();
+        let bytes = resp.bytes().await?.to_vec();
+
+        Ok(Some(SszResponse::Found {
+            bytes,
+            consensus_version,
+        }))
+    }
+
+    /// Sends the request to the nodes in order of preference until one of them succeeds.
+    ///
+    /// With a single node, the request is retried on that node. Otherwise, each node is tried
+    /// once and the whole round is retried if all of them fail with a transient error.
+    async fn _request<T, F, Fut>(&self, request: F) -> ClientResult<Option<T>>
+    where
+        F: Fn(usize, Option<ExponentialBackoff>) -> Fut,
+        Fut: Future<Output = ClientResult<Option<T>>>,
+    {
+        match &self.exp_backoff {
+            Some(exp_backoff) if self.node_pool.len() > 1 => {
+                backoff::future::retry(exp_backoff.clone(), || async {
+                    self._request_any_node(&request, None)
+                        .await
+                        .map_err(|error| {
+                            if error.is_transient() {
+                                backoff::Error::transient(error)
+                            } else {
+                                backoff::Error::permanent(error)
+                            }
+                        })
+                })
+                .await
+            }
+            _ => {
+                self._request_any_node(&request, self.exp_backoff.clone())
+                    .await
+            }
+        }
+    }
+
+    async fn _request_any_node<T, F, Fut>(
+        &self,
+        request: &F,
+        exp_backoff: Option<ExponentialBackoff>,
+    ) -> ClientResult<Option<T>>
+    where
+        F: Fn(usize, Option<ExponentialBackoff>) -> Fut,
+        Fut: Future<Output = ClientResult<Option<T>>>,
+    {
+        let mut last_error = None;
+        let mut is_not_found = false;
+
+        for node in self.node_pool.ranked_nodes() {
+            let base_url = self.node_pool.base_url(node);
+
+            match request(node, exp_backoff.clone()).await {
+                // A node lagging behind may not have recent blocks yet, while the others do
+                Ok(None) if self.node_pool.is_behind_best_node(node) => {
+                    debug!(
+                        target = "beacon_client",
+                        node = %base_url,
+                        "Resource not found on lagging beacon node. Trying the next node…"
+                    );
+
+                    self.node_pool.report_success(node);
+
+                    is_not_found = true;
+                }
+                Ok(response) => {
+                    self.node_pool.report_success(node);
+
+                    return Ok(response);
+                }
+                Err(error) => {
+                    warn!(
+                        target = "beacon_client",
+                        node = %base_url,
+                        ?error,
+                        "Beacon node request failed. Failing over to the next node…"
+                    );
+
+                    self.node_pool.report_failure(node);
+
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        match last_error {
+            Some(error) => Err(error),
+            None if is_not_found => Ok(None),
+            None => Err(anyhow!("No beacon node available").into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use backoff::ExponentialBackoffBuilder;
+    use ethers::types::H256;
+
+    use crate::utils::mock_server::{
+        beacon_node_handler, create_block, create_forked_chain, start_beacon_node, start_server,
+        Response,
+    };
+
+    use super::blob_source::BeaconApiBlobSource;
+    use super::*;
+
+    const UNREACHABLE_NODE_URL: &str = "http://127.0.0.1:1";
+    const DENEB_BLOCK: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/deneb_signed_beacon_block.ssz"
+    ));
+    const BLOB_SIDECARS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blob_sidecars.ssz"
+    ));
+    const JSON_BLOB_SIDECARS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blob_sidecar.json"
+    ));
+    // Slot and root of the block fixtures
+    const SLOT: u32 = 1409759;
+    const BLOCK_ROOT: &str = "0xc7d9a15af18c4e4db0f69d1e3d6b6f2b8b1ffea2291f65bb4134bbc4dece8782";
+
+    fn create_beacon_client(base_urls: Vec<String>) -> BeaconClient {
+        BeaconClient::try_with_client(
+            Client::new(),
+            Config {
+                base_urls,
+                blob_sources: vec![],
+                exp_backoff: None,
+            },
+        )
+        .unwrap()
+    }
+
+    fn create_beacon_client_with_blob_sources(
+        base_url: String,
+        blob_source_urls: Vec<String>,
+    ) -> BeaconClient {
+        BeaconClient::try_with_client(
+            Client::new(),
+            Config {
+                base_urls: vec![base_url],
+                blob_sources: blob_source_urls
+                    .iter()
+                    .map(|url| {
+                        Box::new(
+                            BeaconApiBlobSource::try_with_client(Client::new(), url, None).unwrap(),
+                        ) as Box<dyn BlobSource>
+                    })
+                    .collect(),
+                exp_backoff: None,
+            },
+        )
+        .unwrap()
+    }
+
+    /// Starts a blob archive serving the sidecar fixture, which holds the one at index 1.
+    async fn start_blob_archive() -> String {
+        start_server(|request| {
+            if request.path == format!("/eth/v1/beacon/blob_sidecars/{BLOCK_ROOT}") {
+                Response::json(
+                    serde_json::from_str::<serde_json::Value>(JSON_BLOB_SIDECARS).unwrap(),
+                )
+            } else {
+                Response::not_found()
+            }
+        })
+        .await
+    }
+
+    async fn start_failing_node() -> String {
+        start_server(|_| Response::error(500)).await
+    }
+
+    #[test]
+    fn test_try_with_client_without_nodes() {
+        assert!(BeaconClient::try_with_client(
+            Client::new(),
+            Config {
+                base_urls: vec![],
+                blob_sources: vec![],
+                exp_backoff: None,
+            },
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fail_over_to_next_node() {
+        let beacon_node_url = start_beacon_node(create_forked_chain()).await;
+
+        for failed_node_url in [UNREACHABLE_NODE_URL.to_string(), start_failing_node().await] {
+            let beacon_client =
+                create_beacon_client(vec![failed_node_url, beacon_node_url.clone()]);
+
+            let block_header = beacon_client
+                .get_block_header(&BlockId::Slot(13))
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(block_header.root, H256::from_low_u64_be(213));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_node_is_tried_last() {
+        let beacon_client = create_beacon_client(vec![
+            start_failing_node().await,
+            start_beacon_node(create_forked_chain()).await,
+        ]);
+
+        for _ in 0..3 {
+            beacon_client
+                .get_block_header(&BlockId::Head)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(beacon_client.node_pool.ranked_nodes(), vec![1, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_request_fails_when_every_node_fails() {
+        let beacon_client = create_beacon_client(vec![
+            UNREACHABLE_NODE_URL.to_string(),
+            start_failing_node().await,
+        ]);
+
+        assert!(beacon_client
+            .get_block_header(&BlockId::Head)
+            .await
+            .is_err());
+    }
+
+    /// Starts two nodes answering every header request with the given status and returns how
+    /// many header requests they got before the client gave up.
+    async fn count_header_requests_with_retries(status: u16) -> usize {
+        let header_requests = Arc::new(AtomicUsize::new(0));
+        let mut base_urls = vec![];
+
+        for _ in 0..2 {
+            let header_requests = header_requests.clone();
+
+            base_urls.push(
+                start_server(move |request| {
+                    if request.path.starts_with("/eth/v1/beacon/headers") {
+                        header_requests.fetch_add(1, Ordering::SeqCst);
+                    }
+
+                    Response::error(status)
+                })
+                .await,
+            );
+        }
+
+        let beacon_client = BeaconClient::try_with_client(
+            Client::new(),
+            Config {
+                base_urls,
+                blob_sources: vec![],
+                exp_backoff: Some(
+                    ExponentialBackoffBuilder::default()
+                        .with_initial_interval(Duration::from_millis(10))
+                        .with_max_elapsed_time(Some(Duration::from_millis(300)))
+                        .build(),
+                ),
+            },
+        )
+        .unwrap();
+
+        assert!(beacon_client
+            .get_block_header(&BlockId::Head)
+            .await
+            .is_err());
+
+        header_requests.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn test_retry_nodes_on_server_errors() {
+        assert!(count_header_requests_with_retries(500).await > 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_retries_on_client_errors() {
+        assert_eq!(count_header_requests_with_retries(400).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_not_found_on_lagging_node() {
+        let lagging_chain = vec![create_block(10, 10, 9), create_block(11, 111, 10)];
+        let beacon_client = create_beacon_client(vec![
+            start_beacon_node(lagging_chain).await,
+            start_beacon_node(create_forked_chain()).await,
+        ]);
+
+        // Until its head is known, a node isn't considered to be lagging
+        assert!(beacon_client
+            .get_block_header(&BlockId::Slot(13))
+            .await
+            .unwrap()
+            .is_none());
+
+        beacon_client.check_nodes_health().await;
+
+        let block_header = beacon_client
+            .get_block_header(&BlockId::Slot(13))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(block_header.root, H256::from_low_u64_be(213));
+        assert!(beacon_client
+            .get_block_header(&BlockId::Slot(14))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_check_nodes_health() {
+        let beacon_client = create_beacon_client(vec![
+            UNREACHABLE_NODE_URL.to_string(),
+            start_beacon_node(vec![create_block(1, 1, 0)]).await,
+            start_beacon_node(create_forked_chain()).await,
+        ]);
+
+        for _ in 0..3 {
+            beacon_client.check_nodes_health().await;
+        }
+
+        // The unreachable node keeps failing, while the second one is too far behind the head
+        assert!(!beacon_client.node_pool.is_node_healthy(0));
+        assert!(!beacon_client.node_pool.is_node_healthy(1));
+        assert_eq!(beacon_client.node_pool.ranked_nodes(), vec![2, 0, 1]);
+    }
+
+    /// Starts a node serving the given SSZ response, and answering with JSON when SSZ isn't
+    /// requested.
+    async fn start_ssz_node(ssz_response: fn() -> Response) -> String {
+        let handler = beacon_node_handler(create_forked_chain());
+
+        start_server(move |request| {
+            if request.header("Accept") == Some(SSZ_CONTENT_TYPE) {
+                return ssz_response();
+            }
+
+            handler(request)
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_get_block_as_ssz() {
+        let beacon_client = create_beacon_client(vec![
+            start_ssz_node(|| Response::ssz(DENEB_BLOCK, "deneb")).await,
+        ]);
+
+        let block = beacon_client
+            .get_block(&BlockId::Slot(SLOT))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(block.fork, Fork::Deneb);
+        assert_eq!(block.message.slot, SLOT);
+        assert!(beacon_client.node_pool.is_ssz_supported(0));
+    }
+
+    #[tokio::test]
+    async fn test_get_blobs_as_ssz() {
+        let beacon_client = create_beacon_client(vec![
+            start_ssz_node(|| Response::ssz(BLOB_SIDECARS, "deneb")).await,
+        ]);
+
+        let blobs = beacon_client
+            .get_blobs(&BlockId::Slot(SLOT))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(blobs.len(), 2);
+        assert!(blobs
+            .iter()
+            .all(|blob| blob.signed_block_header.message.slot == SLOT));
+    }
+
+    #[tokio::test]
+    async fn test_ssz_not_found() {
+        let beacon_client = create_beacon_client(vec![start_ssz_node(Response::not_found).await]);
+
+        assert!(beacon_client
+            .get_block(&BlockId::Slot(SLOT))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(beacon_client.node_pool.is_ssz_supported(0));
+    }
+
+    #[tokio::test]
+    async fn test_fall_back_to_json_when_ssz_is_unsupported() {
+        let json_node_url = start_beacon_node(create_forked_chain()).await;
+        let ssz_unacceptable_node_url = start_ssz_node(|| Response::error(406)).await;
+
+        for beacon_node_url in [json_node_url, ssz_unacceptable_node_url] {
+            let beacon_client = create_beacon_client(vec![beacon_node_url]);
+
+            let block = beacon_client
+                .get_block(&BlockId::Slot(13))
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(block.message.slot, 13);
+            assert!(!beacon_client.node_pool.is_ssz_supported(0));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fall_back_to_json_when_ssz_cant_be_decoded() {
+        let beacon_client = create_beacon_client(vec![
+            start_ssz_node(|| Response::ssz(&[0; 100], "deneb")).await,
+        ]);
+
+        let block = beacon_client
+            .get_block(&BlockId::Slot(13))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(block.message.slot, 13);
+        // Only that response is read as JSON
+        assert!(beacon_client.node_pool.is_ssz_supported(0));
+    }
+
+    #[tokio::test]
+    async fn test_get_blobs_from_blob_sources() {
+        let beacon_client = create_beacon_client_with_blob_sources(
+            start_beacon_node(vec![]).await,
+            vec![start_failing_node().await, start_blob_archive().await],
+        );
+
+        assert!(beacon_client.has_blob_sources());
+
+        // Failing sources are skipped
+        let blobs = beacon_client
+            .get_blobs(&BlockId::Root(BLOCK_ROOT.parse().unwrap()))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(blobs.len(), 1);
+        assert_eq!(blobs[0].index, "1");
+    }
+
+    #[tokio::test]
+    async fn test_get_blobs_from_nodes_first() {
+        let beacon_client = create_beacon_client_with_blob_sources(
+            start_ssz_node(|| Response::ssz(BLOB_SIDECARS, "deneb")).await,
+            vec![start_blob_archive().await],
+        );
+
+        let blobs = beacon_client
+            .get_blobs(&BlockId::Root(BLOCK_ROOT.parse().unwrap()))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(blobs.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_blobs_by_slot_from_nodes_only() {
+        // Blob sources are looked up by block root
+        let beacon_client = create_beacon_client_with_blob_sources(
+            start_beacon_node(vec![]).await,
+            vec![start_blob_archive().await],
+        );
+
+        assert!(beacon_client
+            .get_blobs(&BlockId::Slot(SLOT))
+            .await
+            .unwrap()
+            .is_none());
+    }
 }
@@ -0,0 +1,194 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    RwLock,
+};
+
+use reqwest::Url;
+
+/// Consecutive failed requests after which a node is considered unhealthy.
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+/// Slots a node can fall behind the most advanced one before being considered unhealthy.
+const MAX_HEAD_LAG: u32 = 4;
+
+#[derive(Debug, Default, Clone)]
+struct NodeHealth {
+    consecutive_failures: u32,
+    head_slot: Option<u32>,
+}
+
+/// Beacon nodes the requests can be routed to, along with their health.
+#[derive(Debug)]
+pub struct NodePool {
+    base_urls: Vec<Url>,
+    health: RwLock<Vec<NodeHealth>>,
+    events_node: RwLock<Option<usize>>,
+    ssz_unsupported: Vec<AtomicBool>,
+}
+
+impl NodePool {
+    pub fn new(base_urls: Vec<Url>) -> Self {
+        let health = vec![NodeHealth::default(); base_urls.len()];
+        let ssz_unsupported = base_urls.iter().map(|_| AtomicBool::new(false)).collect();
+
+        Self {
+            base_urls,
+            health: RwLock::new(health),
+            events_node: RwLock::new(None),
+            ssz_unsupported,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.base_urls.len()
+    }
+
+    pub fn base_url(&self, node: usize) -> &Url {
+        &self.base_urls[node]
+    }
+
+    /// Nodes in the order they should be tried: healthy ones first, keeping the configured
+    /// order among them so requests stick to the preferred node while it's healthy.
+    pub fn ranked_nodes(&self) -> Vec<usize> {
+        let health = self.health.read().unwrap();
+        let best_head_slot = health.iter().filter_map(|node| node.head_slot).max();
+        let mut nodes = (0..self.base_urls.len()).collect::<Vec<_>>();
+
+        nodes.sort_by_key(|node| !Self::is_healthy(&health[*node], best_head_slot));
+
+        nodes
+    }
+
+    pub fn is_node_healthy(&self, node: usize) -> bool {
+        let health = self.health.read().unwrap();
+        let best_head_slot = health.iter().filter_map(|node| node.head_slot).max();
+
+        Self::is_healthy(&health[node], best_head_slot)
+    }
+
+    /// Whether another node is known to have a more recent head, and so may have blocks this
+    /// one doesn't have yet.
+    pub fn is_behind_best_node(&self, node: usize) -> bool {
+        let health = self.health.read().unwrap();
+        let best_head_slot = health.iter().filter_map(|node| node.head_slot).max();
+
+        match (health[node].head_slot, best_head_slot) {
+            (Some(head_slot), Some(best_head_slot)) => head_slot < best_head_slot,
+            (None, Some(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Node the events subscription is opened on.
+    pub fn events_node(&self) -> Option<usize> {
+        *self.events_node.read().unwrap()
+    }
+
+    pub fn set_events_node(&self, node: usize) {
+        *self.events_node.write().unwrap() = Some(node);
+    }
+
+    pub fn is_ssz_supported(&self, node: usize) -> bool {
+        !self.ssz_unsupported[node].load(Ordering::Relaxed)
+    }
+
+    /// Remembers the node doesn't serve SSZ responses, so JSON is requested right away.
+    pub fn set_ssz_unsupported(&self, node: usize) {
+        self.ssz_unsupported[node].store(true, Ordering::Relaxed);
+    }
+
+    pub fn report_success(&self, node: usize) {
+        self.health.write().unwrap()[node].consecutive_failures = 0;
+    }
+
+    pub fn report_failure(&self, node: usize) {
+        self.health.write().unwrap()[node].consecutive_failures += 1;
+    }
+
+    pub fn report_head_slot(&self, node: usize, head_slot: u32) {
+        self.health.write().unwrap()[node].head_slot = Some(head_slot);
+    }
+
+    fn is_healthy(health: &NodeHealth, best_head_slot: Option<u32>) -> bool {
+        let is_behind_head = match (health.head_slot, best_head_slot) {
+            (Some(head_slot), Some(best_head_slot)) => best_head_slot - head_slot > MAX_HEAD_LAG,
+            _ => false,
+        };
+
+        health.consecutive_failures < MAX_CONSECUTIVE_FAILURES && !is_behind_head
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_node_pool(nodes: usize) -> NodePool {
+        NodePool::new(
+            (0..nodes)
+                .map(|node| Url::parse(&format!("http://node-{node}:5052/eth/")).unwrap())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_ranked_nodes_keep_configured_order_while_healthy() {
+        let node_pool = create_node_pool(3);
+
+        assert_eq!(node_pool.ranked_nodes(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_ranked_nodes_move_failing_nodes_last() {
+        let node_pool = create_node_pool(3);
+
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            node_pool.report_failure(0);
+        }
+
+        assert!(!node_pool.is_node_healthy(0));
+        assert_eq!(node_pool.ranked_nodes(), vec![1, 2, 0]);
+
+        node_pool.report_success(0);
+
+        assert_eq!(node_pool.ranked_nodes(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_ranked_nodes_move_nodes_behind_head_last() {
+        let node_pool = create_node_pool(2);
+
+        node_pool.report_head_slot(0, 100);
+        node_pool.report_head_slot(1, 100 + MAX_HEAD_LAG);
+
+        assert_eq!(node_pool.ranked_nodes(), vec![0, 1]);
+
+        node_pool.report_head_slot(1, 100 + MAX_HEAD_LAG + 1);
+
+        assert_eq!(node_pool.ranked_nodes(), vec![1, 0]);
+    }
+
+    #[test]
+    fn test_is_behind_best_node() {
+        let node_pool = create_node_pool(3);
+
+        assert!(!node_pool.is_behind_best_node(0));
+
+        node_pool.report_head_slot(0, 99);
+        node_pool.report_head_slot(1, 100);
+
+        assert!(node_pool.is_behind_best_node(0));
+        assert!(!node_pool.is_behind_best_node(1));
+        // Nodes whose head is unknown may be behind as well
+        assert!(node_pool.is_behind_best_node(2));
+    }
+
+    #[test]
+    fn test_ssz_support() {
+        let node_pool = create_node_pool(2);
+
+        node_pool.set_ssz_unsupported(1);
+
+        assert!(node_pool.is_ssz_supported(0));
+        assert!(!node_pool.is_ssz_supported(1));
+    }
+}
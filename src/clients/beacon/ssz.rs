@@ -0,0 +1,348 @@
+//! Decoding of the SSZ-encoded responses of the beacon API. Only the fields the indexer uses
+//! are read.
+
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::types::{Bytes, H256};
+
+use super::types::{
+    Blob, Block, BlockBody, BlockHeaderMessage, BlockMessage, ExecutionPayload, Fork,
+    InnerBlockHeader, KZG_COMMITMENT_INCLUSION_PROOF_DEPTH,
+};
+
+const BYTES_PER_OFFSET: usize = 4;
+const BYTES_PER_COMMITMENT: usize = 48;
+const BYTES_PER_BLOB: usize = 131072;
+
+/// Position of the fields within the fixed part of a `BeaconBlock`.
+const BLOCK_SLOT_POSITION: usize = 0;
+const BLOCK_PARENT_ROOT_POSITION: usize = 16;
+const BLOCK_STATE_ROOT_POSITION: usize = 48;
+const BLOCK_BODY_OFFSET_POSITION: usize = 80;
+
+/// Position of the offsets within the fixed part of a `BeaconBlockBody`. Each fork only
+/// appends fields to it: the execution payload in Bellatrix, the BLS to execution changes in
+/// Capella, the blob KZG commitments in Deneb and the execution requests in Electra.
+const BODY_EXECUTION_PAYLOAD_OFFSET_POSITION: usize = 380;
+const BODY_BLS_TO_EXECUTION_CHANGES_OFFSET_POSITION: usize = 384;
+const BODY_BLOB_KZG_COMMITMENTS_OFFSET_POSITION: usize = 388;
+const BODY_EXECUTION_REQUESTS_OFFSET_POSITION: usize = 392;
+
+const EXECUTION_PAYLOAD_BLOCK_HASH_POSITION: usize = 472;
+
+/// Size of a `BlobSidecar`: index, blob, commitment, proof, signed block header and
+/// commitment inclusion proof.
+const BLOB_SIDECAR_SIZE: usize = 8
+    + BYTES_PER_BLOB
+    + BYTES_PER_COMMITMENT
+    + 48
+    + SIGNED_BLOCK_HEADER_SIZE
+    + KZG_COMMITMENT_INCLUSION_PROOF_DEPTH * 32;
+const SIGNED_BLOCK_HEADER_SIZE: usize = 112 + 96;
+
+/// Decodes a `SignedBeaconBlock` of the given fork.
+pub fn decode_signed_beacon_block(bytes: &[u8], fork: Fork) -> Result<Block> {
+    let message = bytes
+        .get(read_offset(bytes, 0)?..)
+        .context("Block message out of bounds")?;
+    let slot = read_u64(message, BLOCK_SLOT_POSITION)?;
+    let parent_root = read_root(message, BLOCK_PARENT_ROOT_POSITION)?;
+    let state_root = read_root(message, BLOCK_STATE_ROOT_POSITION)?;
+    let body = message
+        .get(read_offset(message, BLOCK_BODY_OFFSET_POSITION)?..)
+        .context("Block body out of bounds")?;
+
+    // Each variable-size field ends where the next one starts, and the last one at the end of
+    // the body
+    let body = match fork {
+        Fork::Phase0 | Fork::Altair => BlockBody::PreMerge,
+        Fork::Bellatrix => BlockBody::PreDeneb {
+            execution_payload: read_execution_payload(body, body.len())?,
+        },
+        Fork::Capella => BlockBody::PreDeneb {
+            execution_payload: read_execution_payload(
+                body,
+                read_offset(body, BODY_BLS_TO_EXECUTION_CHANGES_OFFSET_POSITION)?,
+            )?,
+        },
+        Fork::Deneb | Fork::Electra | Fork::Fulu => {
+            let commitments_end = if fork == Fork::Deneb {
+                body.len()
+            } else {
+                read_offset(body, BODY_EXECUTION_REQUESTS_OFFSET_POSITION)?
+            };
+            let commitments = read_slice(
+                body,
+                read_offset(body, BODY_BLOB_KZG_COMMITMENTS_OFFSET_POSITION)?,
+                commitments_end,
+            )?;
+
+            if !commitments.len().is_multiple_of(BYTES_PER_COMMITMENT) {
+                bail!("Invalid blob KZG commitments list length");
+            }
+
+            BlockBody::Deneb {
+                execution_payload: read_execution_payload(
+                    body,
+                    read_offset(body, BODY_BLS_TO_EXECUTION_CHANGES_OFFSET_POSITION)?,
+                )?,
+                blob_kzg_commitments: commitments
+                    .chunks_exact(BYTES_PER_COMMITMENT)
+                    .map(to_hex)
+                    .collect(),
+            }
+        }
+    };
+
+    Ok(Block {
+        fork,
+        message: BlockMessage {
+            slot: to_u32(slot)?,
+            parent_root,
+            state_root,
+            body,
+        },
+    })
+}
+
+fn read_execution_payload(body: &[u8], end: usize) -> Result<ExecutionPayload> {
+    let execution_payload = read_slice(
+        body,
+        read_offset(body, BODY_EXECUTION_PAYLOAD_OFFSET_POSITION)?,
+        end,
+    )?;
+
+    Ok(ExecutionPayload {
+        block_hash: read_root(execution_payload, EXECUTION_PAYLOAD_BLOCK_HASH_POSITION)?,
+    })
+}
+
+/// Decodes a list of `BlobSidecar`s, which being fixed-size are simply concatenated.
+pub fn decode_blob_sidecars(bytes: &[u8]) -> Result<Vec<Blob>> {
+    if !bytes.len().is_multiple_of(BLOB_SIDECAR_SIZE) {
+        bail!("Invalid blob sidecars list length {}", bytes.len());
+    }
+
+    bytes
+        .chunks_exact(BLOB_SIDECAR_SIZE)
+        .map(decode_blob_sidecar)
+        .collect()
+}
+
+fn decode_blob_sidecar(bytes: &[u8]) -> Result<Blob> {
+    let index = read_u64(bytes, 0)?;
+    let blob = &bytes[8..8 + BYTES_PER_BLOB];
+    let kzg_commitment = &bytes[8 + BYTES_PER_BLOB..8 + BYTES_PER_BLOB + BYTES_PER_COMMITMENT];
+    let kzg_proof_position = 8 + BYTES_PER_BLOB + BYTES_PER_COMMITMENT;
+    let kzg_proof = &bytes[kzg_proof_position..kzg_proof_position + 48];
+    let header = &bytes[kzg_proof_position + 48..];
+    let inclusion_proof = &bytes[kzg_proof_position + 48 + SIGNED_BLOCK_HEADER_SIZE..];
+
+    Ok(Blob {
+        index: index.to_string(),
+        kzg_commitment: to_hex(kzg_commitment),
+        kzg_proof: to_hex(kzg_proof),
+        blob: Bytes::from(blob.to_vec()),
+        signed_block_header: InnerBlockHeader {
+            message: BlockHeaderMessage {
+                slot: to_u32(read_u64(header, 0)?)?,
+                proposer_index: to_u32(read_u64(header, 8)?)?,
+                parent_root: read_root(header, 16)?,
+                state_root: read_root(header, 48)?,
+                body_root: read_root(header, 80)?,
+            },
+        },
+        kzg_commitment_inclusion_proof: inclusion_proof
+            .chunks_exact(32)
+            .map(H256::from_slice)
+            .collect(),
+    })
+}
+
+fn read_slice(bytes: &[u8], start: usize, end: usize) -> Result<&[u8]> {
+    bytes
+        .get(start..end)
+        .with_context(|| format!("Range {start}..{end} out of bounds"))
+}
+
+fn read_u64(bytes: &[u8], position: usize) -> Result<u64> {
+    let value = read_slice(bytes, position, position + 8)?;
+
+    Ok(u64::from_le_bytes(value.try_into()?))
+}
+
+fn read_offset(bytes: &[u8], position: usize) -> Result<usize> {
+    let offset = read_slice(bytes, position, position + BYTES_PER_OFFSET)?;
+
+    Ok(u32::from_le_bytes(offset.try_into()?) as usize)
+}
+
+fn read_root(bytes: &[u8], position: usize) -> Result<H256> {
+    Ok(H256::from_slice(read_slice(
+        bytes,
+        position,
+        position + 32,
+    )?))
+}
+
+fn to_u32(value: u64) -> Result<u32> {
+    u32::try_from(value).map_err(|_| anyhow!("Value {value} doesn't fit in 32 bits"))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::types::BlobsResponse;
+    use super::*;
+
+    const DENEB_BLOCK: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/deneb_signed_beacon_block.ssz"
+    ));
+    const ELECTRA_BLOCK: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/electra_signed_beacon_block.ssz"
+    ));
+    const CAPELLA_BLOCK: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/capella_signed_beacon_block.ssz"
+    ));
+    const ALTAIR_BLOCK: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/altair_signed_beacon_block.ssz"
+    ));
+    const BLOB_SIDECARS: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blob_sidecars.ssz"
+    ));
+
+    const SLOT: u32 = 1409759;
+    const PARENT_ROOT: &str = "0x83c2e78d90e9d4031c0de0db5782143ac38e0e7f41ad98f8b97dff90a270e6df";
+    const STATE_ROOT: &str = "0x11122c310a39307f2d3150f9f368599dd8c5771786479314ed527002f10e6548";
+    const EXECUTION_BLOCK_HASH: &str =
+        "0x2982946c9fb44951fd9f65f73446a49d4fd6e0140b3f3ea857ff50eac7be69d7";
+    const COMMITMENTS: [&str; 6] = [
+        "0xadfebfe0d08b0b6f4e95bff79e2e029406e40781cca70c13f43104b81a03bf682ecfca570370b2836cb04251ef6656ae",
+        "0x95775c4349d5b03e71bba6452d79b79a88842c924ac480042fc7c20e8e5a28068eac650dc8fc9789c6515ca84b7514ef",
+        "0xa2a733019fa96850badb3cd4c2dbfde15fde2dcd907cc0f9d5dfeeee9dfbd2239c7e60b16353a0f9d8735d50169bbea8",
+        "0x800b1a88523315c61947af00ba9a1e655e19a2b4cb062894dce7be47b2783f45ee2728d7c28db28e4f80ea5e2ec4341c",
+        "0x8df0858590fa455464c628a3ac65c32f6388ec4de836a3674bef168fb9fbcb21709750e82b48e27825467468dff3d0d1",
+        "0xa20c71d1985996098aa63e8b5dc7b7fedb70de31478fe309dad3ac0e9b6d28d82be8e5e543021a0203dc785742e94b2f",
+    ];
+
+    fn assert_block(block: &Block, fork: Fork, commitments: &[&str]) {
+        assert_eq!(block.fork, fork);
+        assert_eq!(block.message.slot, SLOT);
+        assert_eq!(block.message.parent_root, PARENT_ROOT.parse().unwrap());
+        assert_eq!(block.message.state_root, STATE_ROOT.parse().unwrap());
+
+        match &block.message.body {
+            BlockBody::Deneb {
+                execution_payload,
+                blob_kzg_commitments,
+            } => {
+                assert_eq!(
+                    execution_payload.block_hash,
+                    EXECUTION_BLOCK_HASH.parse().unwrap()
+                );
+                assert_eq!(blob_kzg_commitments, commitments);
+            }
+            body => panic!("Unexpected block body {body:?}"),
+        }
+    }
+
+    #[test]
+    fn test_decode_deneb_signed_beacon_block() {
+        let block = decode_signed_beacon_block(DENEB_BLOCK, Fork::Deneb).unwrap();
+
+        assert_block(&block, Fork::Deneb, &COMMITMENTS);
+    }
+
+    #[test]
+    fn test_decode_electra_signed_beacon_block() {
+        // The execution requests following the commitments must not be read as commitments
+        let block = decode_signed_beacon_block(ELECTRA_BLOCK, Fork::Electra).unwrap();
+
+        assert_block(&block, Fork::Electra, &COMMITMENTS[..2]);
+    }
+
+    #[test]
+    fn test_decode_capella_signed_beacon_block() {
+        let block = decode_signed_beacon_block(CAPELLA_BLOCK, Fork::Capella).unwrap();
+
+        assert_eq!(block.fork, Fork::Capella);
+        assert_eq!(block.message.slot, SLOT);
+        assert_eq!(block.message.parent_root, PARENT_ROOT.parse().unwrap());
+
+        match &block.message.body {
+            BlockBody::PreDeneb { execution_payload } => assert_eq!(
+                execution_payload.block_hash,
+                EXECUTION_BLOCK_HASH.parse().unwrap()
+            ),
+            body => panic!("Unexpected block body {body:?}"),
+        }
+    }
+
+    #[test]
+    fn test_decode_altair_signed_beacon_block() {
+        let block = decode_signed_beacon_block(ALTAIR_BLOCK, Fork::Altair).unwrap();
+
+        assert_eq!(block.fork, Fork::Altair);
+        assert_eq!(block.message.slot, SLOT);
+        assert_eq!(block.message.parent_root, PARENT_ROOT.parse().unwrap());
+        assert!(matches!(block.message.body, BlockBody::PreMerge));
+    }
+
+    #[test]
+    fn test_decode_signed_beacon_block_of_other_fork() {
+        assert!(decode_signed_beacon_block(ELECTRA_BLOCK, Fork::Deneb).is_err());
+    }
+
+    #[test]
+    fn test_decode_truncated_signed_beacon_block() {
+        assert!(decode_signed_beacon_block(&DENEB_BLOCK[..1000], Fork::Deneb).is_err());
+        assert!(decode_signed_beacon_block(&[], Fork::Deneb).is_err());
+    }
+
+    #[test]
+    fn test_decode_blob_sidecars() {
+        let blobs = decode_blob_sidecars(BLOB_SIDECARS).unwrap();
+        let response: BlobsResponse = serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/blob_sidecar.json"
+        )))
+        .unwrap();
+        let expected_blob = &response.data[0];
+
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(blobs[0].index, "0");
+        assert_eq!(blobs[0].kzg_commitment, COMMITMENTS[0]);
+
+        let blob = &blobs[1];
+        let header = &blob.signed_block_header.message;
+        let expected_header = &expected_blob.signed_block_header.message;
+
+        assert_eq!(blob.index, expected_blob.index);
+        assert_eq!(blob.kzg_commitment, expected_blob.kzg_commitment);
+        assert_eq!(blob.kzg_proof, expected_blob.kzg_proof);
+        assert_eq!(blob.blob, expected_blob.blob);
+        assert_eq!(header.slot, SLOT);
+        assert_eq!(header.slot, expected_header.slot);
+        assert_eq!(header.proposer_index, expected_header.proposer_index);
+        assert_eq!(header.parent_root, expected_header.parent_root);
+        assert_eq!(header.state_root, expected_header.state_root);
+        assert_eq!(header.body_root, expected_header.body_root);
+        assert_eq!(
+            blob.kzg_commitment_inclusion_proof,
+            expected_blob.kzg_commitment_inclusion_proof
+        );
+    }
+
+    #[test]
+    fn test_decode_blob_sidecars_with_invalid_length() {
+        assert!(decode_blob_sidecars(&[]).unwrap().is_empty());
+        assert!(decode_blob_sidecars(&BLOB_SIDECARS[..BLOB_SIDECAR_SIZE + 1]).is_err());
+    }
+}
@@ -1,21 +1,40 @@
 use std::{fmt, str::FromStr};
 
+use anyhow::anyhow;
 use ethers::types::{Bytes, H256};
 use serde::{Deserialize, Serialize};
 
-use crate::slots_processor::BlockData;
+/// Depth of the Merkle branch proving a blob sidecar commitment is included in the block body.
+pub const KZG_COMMITMENT_INCLUSION_PROOF_DEPTH: usize = 17;
 
 #[derive(Serialize, Debug, Clone)]
 pub enum BlockId {
     Head,
+    Genesis,
     Finalized,
+    Justified,
     Slot(u32),
+    Root(H256),
 }
 
 #[derive(Serialize, Debug)]
 pub enum Topic {
     Head,
     FinalizedCheckpoint,
+    ChainReorg,
+}
+
+/// Consensus layer forks, as named by the `Eth-Consensus-Version` header.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Fork {
+    Phase0,
+    Altair,
+    Bellatrix,
+    Capella,
+    Deneb,
+    Electra,
+    Fulu,
 }
 
 #[derive(Deserialize, Debug)]
@@ -23,34 +42,81 @@ pub struct ExecutionPayload {
     pub block_hash: H256,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct BlockBody {
-    pub execution_payload: Option<ExecutionPayload>,
-    pub blob_kzg_commitments: Option<Vec<String>>,
+/// Contents of a block body the indexer relies on, which depend on the fork of the block.
+#[derive(Debug)]
+pub enum BlockBody {
+    /// Phase0 and Altair blocks, prior to the merge.
+    PreMerge,
+    /// Bellatrix and Capella blocks, which have an execution payload but no blobs.
+    PreDeneb { execution_payload: ExecutionPayload },
+    /// Blocks from Deneb onwards.
+    Deneb {
+        execution_payload: ExecutionPayload,
+        blob_kzg_commitments: Vec<String>,
+    },
 }
-#[derive(Deserialize, Debug)]
+
+impl BlockBody {
+    pub fn execution_payload(&self) -> Option<&ExecutionPayload> {
+        match self {
+            BlockBody::PreMerge => None,
+            BlockBody::PreDeneb { execution_payload }
+            | BlockBody::Deneb {
+                execution_payload, ..
+            } => Some(execution_payload),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct BlockMessage {
-    #[serde(deserialize_with = "deserialize_slot")]
     pub slot: u32,
-    pub body: BlockBody,
     pub parent_root: H256,
+    pub state_root: H256,
+    pub body: BlockBody,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Debug)]
 pub struct Block {
+    pub fork: Fork,
     pub message: BlockMessage,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct BlockResponse {
-    pub data: Block,
+    pub version: Fork,
+    pub data: RawBlock,
+}
+
+/// Block as returned by the beacon API, whose fields depend on the fork it belongs to.
+#[derive(Deserialize, Debug)]
+pub struct RawBlock {
+    pub message: RawBlockMessage,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RawBlockMessage {
+    #[serde(deserialize_with = "deserialize_slot")]
+    pub slot: u32,
+    pub parent_root: H256,
+    pub state_root: H256,
+    pub body: RawBlockBody,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RawBlockBody {
+    pub execution_payload: Option<ExecutionPayload>,
+    pub blob_kzg_commitments: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Blob {
     pub index: String,
     pub kzg_commitment: String,
+    pub kzg_proof: String,
     pub blob: Bytes,
+    pub signed_block_header: InnerBlockHeader,
+    pub kzg_commitment_inclusion_proof: Vec<H256>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -78,13 +144,154 @@ pub struct BlockHeaderMessage {
     pub parent_root: H256,
     #[serde(deserialize_with = "deserialize_slot")]
     pub slot: u32,
+    #[serde(deserialize_with = "deserialize_slot")]
+    pub proposer_index: u32,
+    pub state_root: H256,
+    pub body_root: H256,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SyncStatusResponse {
+    pub data: SyncStatus,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SyncStatus {
+    #[serde(deserialize_with = "deserialize_slot")]
+    pub head_slot: u32,
+    #[serde(deserialize_with = "deserialize_slot")]
+    pub sync_distance: u32,
+    pub is_syncing: bool,
+    #[serde(default)]
+    pub is_optimistic: bool,
+    #[serde(default)]
+    pub el_offline: bool,
+}
+
+impl SyncStatus {
+    /// Whether the node is synced and has fully verified its head through its execution node.
+    pub fn is_synced(&self) -> bool {
+        !self.is_syncing && !self.is_optimistic && !self.el_offline
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SpecResponse {
+    pub data: Spec,
+}
+
+/// Chain configuration values the indexer relies on. The beacon API returns many more.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Spec {
+    #[serde(rename = "SECONDS_PER_SLOT", deserialize_with = "deserialize_u64")]
+    pub seconds_per_slot: u64,
+    #[serde(rename = "SLOTS_PER_EPOCH", deserialize_with = "deserialize_u64")]
+    pub slots_per_epoch: u64,
+    #[serde(rename = "ALTAIR_FORK_EPOCH", deserialize_with = "deserialize_u64")]
+    pub altair_fork_epoch: u64,
+    #[serde(rename = "BELLATRIX_FORK_EPOCH", deserialize_with = "deserialize_u64")]
+    pub bellatrix_fork_epoch: u64,
+    #[serde(rename = "CAPELLA_FORK_EPOCH", deserialize_with = "deserialize_u64")]
+    pub capella_fork_epoch: u64,
+    #[serde(rename = "DENEB_FORK_EPOCH", deserialize_with = "deserialize_u64")]
+    pub deneb_fork_epoch: u64,
+    #[serde(
+        rename = "ELECTRA_FORK_EPOCH",
+        deserialize_with = "deserialize_u64",
+        default = "far_future_epoch"
+    )]
+    pub electra_fork_epoch: u64,
+    #[serde(
+        rename = "FULU_FORK_EPOCH",
+        deserialize_with = "deserialize_u64",
+        default = "far_future_epoch"
+    )]
+    pub fulu_fork_epoch: u64,
+    #[serde(
+        rename = "MIN_EPOCHS_FOR_BLOB_SIDECARS_REQUESTS",
+        deserialize_with = "deserialize_u64",
+        default = "default_min_epochs_for_blob_sidecars_requests"
+    )]
+    pub min_epochs_for_blob_sidecars_requests: u64,
+    #[serde(
+        rename = "MAX_BLOBS_PER_BLOCK",
+        deserialize_with = "deserialize_u64",
+        default = "default_max_blobs_per_block"
+    )]
+    pub max_blobs_per_block: u64,
+    #[serde(
+        rename = "MAX_BLOBS_PER_BLOCK_ELECTRA",
+        deserialize_with = "deserialize_u64",
+        default = "default_max_blobs_per_block_electra"
+    )]
+    pub max_blobs_per_block_electra: u64,
+    /// Blob limit changes from Fulu onwards.
+    #[serde(rename = "BLOB_SCHEDULE", default)]
+    pub blob_schedule: Vec<BlobScheduleEntry>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BlobScheduleEntry {
+    #[serde(rename = "EPOCH", deserialize_with = "deserialize_u64")]
+    pub epoch: u64,
+    #[serde(rename = "MAX_BLOBS_PER_BLOCK", deserialize_with = "deserialize_u64")]
+    pub max_blobs_per_block: u64,
+}
+
+/// Epoch of the forks nodes don't know about yet, and therefore aren't scheduled.
+fn far_future_epoch() -> u64 {
+    u64::MAX
+}
+
+/// Mainnet value, for nodes that don't expose it.
+fn default_min_epochs_for_blob_sidecars_requests() -> u64 {
+    4096
+}
+
+/// Mainnet value, for nodes that don't expose it.
+fn default_max_blobs_per_block() -> u64 {
+    6
+}
+
+/// Mainnet value, for nodes that don't expose it.
+fn default_max_blobs_per_block_electra() -> u64 {
+    9
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GenesisResponse {
+    pub data: Genesis,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Genesis {
+    #[serde(deserialize_with = "deserialize_u64")]
+    pub genesis_time: u64,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct HeadBlockEventData {
     #[serde(deserialize_with = "deserialize_slot")]
     pub slot: u32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FinalizedCheckpointEventData {
     pub block: H256,
+    #[serde(deserialize_with = "deserialize_slot")]
+    pub epoch: u32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChainReorgEventData {
+    #[serde(deserialize_with = "deserialize_slot")]
+    pub slot: u32,
+    #[serde(deserialize_with = "deserialize_slot")]
+    pub depth: u32,
+    pub old_head_block: H256,
+    pub new_head_block: H256,
+    pub old_head_state: H256,
+    pub new_head_state: H256,
 }
 
 fn deserialize_slot<'de, D>(deserializer: D) -> Result<u32, D::Error>
@@ -96,12 +303,24 @@ where
     slot.parse::<u32>().map_err(serde::de::Error::custom)
 }
 
+fn deserialize_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+
+    value.parse::<u64>().map_err(serde::de::Error::custom)
+}
+
 impl fmt::Display for BlockId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             BlockId::Head => write!(f, "head"),
+            BlockId::Genesis => write!(f, "genesis"),
             BlockId::Finalized => write!(f, "finalized"),
+            BlockId::Justified => write!(f, "justified"),
             BlockId::Slot(slot) => write!(f, "{}", slot),
+            BlockId::Root(root) => write!(f, "{:#x}", root),
         }
     }
 }
@@ -112,31 +331,393 @@ impl FromStr for BlockId {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "head" => Ok(BlockId::Head),
+            "genesis" => Ok(BlockId::Genesis),
             "finalized" => Ok(BlockId::Finalized),
+            "justified" => Ok(BlockId::Justified),
+            _ if s.starts_with("0x") => match s.parse::<H256>() {
+                Ok(root) if s.len() == 66 => Ok(BlockId::Root(root)),
+                _ => Err(format!(
+                    "Invalid block root {s}. Expected a 0x-prefixed 32-byte hex string."
+                )),
+            },
             _ => match s.parse::<u32>() {
                 Ok(num) => Ok(BlockId::Slot(num)),
-                Err(_) => {
-                    Err("Invalid block ID. Expected 'head', 'finalized' or a number.".to_string())
-                }
+                Err(_) => Err(
+                    "Invalid block ID. Expected 'head', 'genesis', 'finalized', 'justified', a block root or a number."
+                        .to_string(),
+                ),
             },
         }
     }
 }
 
+impl fmt::Display for Fork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Fork::Phase0 => "phase0",
+            Fork::Altair => "altair",
+            Fork::Bellatrix => "bellatrix",
+            Fork::Capella => "capella",
+            Fork::Deneb => "deneb",
+            Fork::Electra => "electra",
+            Fork::Fulu => "fulu",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Fork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "phase0" => Ok(Fork::Phase0),
+            "altair" => Ok(Fork::Altair),
+            "bellatrix" => Ok(Fork::Bellatrix),
+            "capella" => Ok(Fork::Capella),
+            "deneb" => Ok(Fork::Deneb),
+            "electra" => Ok(Fork::Electra),
+            "fulu" => Ok(Fork::Fulu),
+            _ => Err(format!("Unknown fork {s}")),
+        }
+    }
+}
+
+impl TryFrom<BlockResponse> for Block {
+    type Error = anyhow::Error;
+
+    fn try_from(response: BlockResponse) -> Result<Self, Self::Error> {
+        let fork = response.version;
+        let RawBlockMessage {
+            slot,
+            parent_root,
+            state_root,
+            body,
+        } = response.data.message;
+
+        let body = match fork {
+            Fork::Phase0 | Fork::Altair => BlockBody::PreMerge,
+            Fork::Bellatrix | Fork::Capella => BlockBody::PreDeneb {
+                execution_payload: body.execution_payload.ok_or_else(|| {
+                    anyhow!("{fork} block at slot {slot} has no execution payload")
+                })?,
+            },
+            Fork::Deneb | Fork::Electra | Fork::Fulu => BlockBody::Deneb {
+                execution_payload: body.execution_payload.ok_or_else(|| {
+                    anyhow!("{fork} block at slot {slot} has no execution payload")
+                })?,
+                blob_kzg_commitments: body.blob_kzg_commitments.ok_or_else(|| {
+                    anyhow!("{fork} block at slot {slot} has no blob KZG commitments")
+                })?,
+            },
+        };
+
+        Ok(Block {
+            fork,
+            message: BlockMessage {
+                slot,
+                parent_root,
+                state_root,
+                body,
+            },
+        })
+    }
+}
+
 impl From<&Topic> for String {
     fn from(value: &Topic) -> Self {
         match value {
             Topic::Head => String::from("head"),
             Topic::FinalizedCheckpoint => String::from("finalized_checkpoint"),
+            Topic::ChainReorg => String::from("chain_reorg"),
         }
     }
 }
 
-impl From<HeadBlockEventData> for BlockData {
-    fn from(event_data: HeadBlockEventData) -> Self {
-        Self {
-            root: event_data.block,
-            slot: event_data.slot,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Holesky block at slot 1409759, the one of the sidecar fixtures
+    const ROOT: &str = "0xc7d9a15af18c4e4db0f69d1e3d6b6f2b8b1ffea2291f65bb4134bbc4dece8782";
+    const PARENT_ROOT: &str = "0x83c2e78d90e9d4031c0de0db5782143ac38e0e7f41ad98f8b97dff90a270e6df";
+    const STATE_ROOT: &str = "0x11122c310a39307f2d3150f9f368599dd8c5771786479314ed527002f10e6548";
+    const EXECUTION_BLOCK_HASH: &str =
+        "0x2982946c9fb44951fd9f65f73446a49d4fd6e0140b3f3ea857ff50eac7be69d7";
+    const COMMITMENT: &str = "0x95775c4349d5b03e71bba6452d79b79a88842c924ac480042fc7c20e8e5a28068eac650dc8fc9789c6515ca84b7514ef";
+
+    #[test]
+    fn test_parse_named_block_ids() {
+        assert!(matches!("head".parse(), Ok(BlockId::Head)));
+        assert!(matches!("genesis".parse(), Ok(BlockId::Genesis)));
+        assert!(matches!("finalized".parse(), Ok(BlockId::Finalized)));
+        assert!(matches!("justified".parse(), Ok(BlockId::Justified)));
+    }
+
+    #[test]
+    fn test_parse_slot_block_id() {
+        assert!(matches!("0".parse(), Ok(BlockId::Slot(0))));
+        assert!(matches!("8626176".parse(), Ok(BlockId::Slot(8626176))));
+        assert!("-1".parse::<BlockId>().is_err());
+        assert!("4294967296".parse::<BlockId>().is_err());
+        assert!("latest".parse::<BlockId>().is_err());
+    }
+
+    #[test]
+    fn test_parse_root_block_id() {
+        match ROOT.parse::<BlockId>() {
+            Ok(BlockId::Root(root)) => assert_eq!(format!("{root:#x}"), ROOT),
+            block_id => panic!("Unexpected block ID {block_id:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_root_block_id_with_invalid_length() {
+        for root in [&ROOT[..65], &format!("{ROOT}00"), "0x"] {
+            let error = root.parse::<BlockId>().unwrap_err();
+
+            assert!(error.starts_with("Invalid block root"), "{root}: {error}");
+        }
+    }
+
+    #[test]
+    fn test_parse_root_block_id_with_invalid_hex() {
+        let root = format!("0x{}", "zz".repeat(32));
+
+        assert!(root.parse::<BlockId>().is_err());
+    }
+
+    #[test]
+    fn test_block_id_display_round_trip() {
+        for block_id in ["head", "genesis", "finalized", "justified", "123", ROOT] {
+            assert_eq!(block_id.parse::<BlockId>().unwrap().to_string(), block_id);
+        }
+    }
+
+    fn parse_block(version: &str, body: &str) -> anyhow::Result<Block> {
+        let response = serde_json::from_str::<BlockResponse>(&format!(
+            r#"{{
+                "version": "{version}",
+                "data": {{
+                    "message": {{
+                        "slot": "1409759",
+                        "parent_root": "{PARENT_ROOT}",
+                        "state_root": "{STATE_ROOT}",
+                        "body": {body}
+                    }}
+                }}
+            }}"#
+        ))?;
+
+        response.try_into()
+    }
+
+    fn deneb_body() -> String {
+        format!(
+            r#"{{
+                "execution_payload": {{ "block_hash": "{EXECUTION_BLOCK_HASH}" }},
+                "blob_kzg_commitments": ["{COMMITMENT}"]
+            }}"#
+        )
+    }
+
+    #[test]
+    fn test_fork_display_round_trip() {
+        for fork in [
+            Fork::Phase0,
+            Fork::Altair,
+            Fork::Bellatrix,
+            Fork::Capella,
+            Fork::Deneb,
+            Fork::Electra,
+            Fork::Fulu,
+        ] {
+            assert_eq!(fork.to_string().parse::<Fork>(), Ok(fork));
+        }
+    }
+
+    #[test]
+    fn test_parse_fork() {
+        // The `Eth-Consensus-Version` header isn't always lowercase
+        assert_eq!("Deneb".parse::<Fork>(), Ok(Fork::Deneb));
+        assert!("verkle".parse::<Fork>().is_err());
+    }
+
+    #[test]
+    fn test_forks_are_ordered() {
+        assert!(Fork::Phase0 < Fork::Altair);
+        assert!(Fork::Capella < Fork::Deneb);
+        assert!(Fork::Deneb < Fork::Electra);
+        assert!(Fork::Electra < Fork::Fulu);
+    }
+
+    #[test]
+    fn test_deneb_onwards_blocks() {
+        for version in ["deneb", "electra", "fulu"] {
+            let block = parse_block(version, &deneb_body()).unwrap();
+
+            assert_eq!(block.fork, version.parse().unwrap());
+            assert_eq!(block.message.slot, 1409759);
+            assert_eq!(block.message.parent_root, PARENT_ROOT.parse().unwrap());
+            assert_eq!(block.message.state_root, STATE_ROOT.parse().unwrap());
+
+            match block.message.body {
+                BlockBody::Deneb {
+                    execution_payload,
+                    blob_kzg_commitments,
+                } => {
+                    assert_eq!(
+                        execution_payload.block_hash,
+                        EXECUTION_BLOCK_HASH.parse().unwrap()
+                    );
+                    assert_eq!(blob_kzg_commitments, vec![COMMITMENT.to_string()]);
+                }
+                body => panic!("Unexpected {version} block body {body:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_pre_deneb_blocks() {
+        let body =
+            format!(r#"{{ "execution_payload": {{ "block_hash": "{EXECUTION_BLOCK_HASH}" }} }}"#);
+
+        for version in ["bellatrix", "capella"] {
+            let block = parse_block(version, &body).unwrap();
+
+            assert!(matches!(block.message.body, BlockBody::PreDeneb { .. }));
+            assert_eq!(
+                block.message.body.execution_payload().unwrap().block_hash,
+                EXECUTION_BLOCK_HASH.parse().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_pre_merge_blocks() {
+        for version in ["phase0", "altair"] {
+            let block = parse_block(version, "{}").unwrap();
+
+            assert!(matches!(block.message.body, BlockBody::PreMerge));
+            assert!(block.message.body.execution_payload().is_none());
+        }
+    }
+
+    #[test]
+    fn test_blocks_missing_fork_fields() {
+        let body =
+            format!(r#"{{ "execution_payload": {{ "block_hash": "{EXECUTION_BLOCK_HASH}" }} }}"#);
+
+        assert!(parse_block("capella", "{}").is_err());
+        assert!(parse_block("deneb", &body).is_err());
+        assert!(parse_block("electra", &body).is_err());
+        assert!(parse_block("verkle", &deneb_body()).is_err());
+    }
+
+    #[test]
+    fn test_finalized_checkpoint_event_data() {
+        // Example from the beacon API events specification
+        let event_data = serde_json::from_str::<FinalizedCheckpointEventData>(
+            r#"{
+                "block": "0x9a2fefd2fdb57f74993c7780ea5b9030d2897b615b89f808011ca5aebed54eaf",
+                "state": "0x600e852a08c1200654ddf11025f1ceacb3c2e74bdd5c630cde0838b2591b69f9",
+                "epoch": "2",
+                "execution_optimistic": false
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(event_data.epoch, 2);
+        assert_eq!(
+            event_data.block,
+            "0x9a2fefd2fdb57f74993c7780ea5b9030d2897b615b89f808011ca5aebed54eaf"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            String::from(&Topic::FinalizedCheckpoint),
+            "finalized_checkpoint"
+        );
+    }
+
+    #[test]
+    fn test_chain_reorg_event_data() {
+        // Example from the beacon API events specification
+        let event_data = serde_json::from_str::<ChainReorgEventData>(
+            r#"{
+                "slot": "200",
+                "depth": "50",
+                "old_head_block": "0x9a2fefd2fdb57f74993c7780ea5b9030d2897b615b89f808011ca5aebed54eaf",
+                "new_head_block": "0x76262e91970d375a19bfe8a867288d7b9cde43c8635f598d93d39d041706fc76",
+                "old_head_state": "0x9a2fefd2fdb57f74993c7780ea5b9030d2897b615b89f808011ca5aebed54eaf",
+                "new_head_state": "0x600e852a08c1200654ddf11025f1ceacb3c2e74bdd5c630cde0838b2591b69f9",
+                "epoch": "2",
+                "execution_optimistic": false
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(event_data.slot, 200);
+        assert_eq!(event_data.depth, 50);
+        assert_eq!(
+            event_data.new_head_block,
+            "0x76262e91970d375a19bfe8a867288d7b9cde43c8635f598d93d39d041706fc76"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(String::from(&Topic::ChainReorg), "chain_reorg");
+    }
+
+    fn parse_sync_status(data: &str) -> SyncStatus {
+        serde_json::from_str::<SyncStatusResponse>(&format!(r#"{{ "data": {data} }}"#))
+            .unwrap()
+            .data
+    }
+
+    #[test]
+    fn test_sync_status() {
+        let sync_status = parse_sync_status(
+            r#"{
+                "head_slot": "1409759",
+                "sync_distance": "0",
+                "is_syncing": false,
+                "is_optimistic": false,
+                "el_offline": false
+            }"#,
+        );
+
+        assert_eq!(sync_status.head_slot, 1409759);
+        assert!(sync_status.is_synced());
+    }
+
+    #[test]
+    fn test_sync_status_without_optional_fields() {
+        // Older nodes don't report whether they are optimistic or their execution node is offline
+        let sync_status =
+            parse_sync_status(r#"{ "head_slot": "1", "sync_distance": "0", "is_syncing": false }"#);
+
+        assert!(sync_status.is_synced());
+    }
+
+    #[test]
+    fn test_unsynced_sync_status() {
+        for (is_syncing, is_optimistic, el_offline) in [
+            (true, false, false),
+            (false, true, false),
+            (false, false, true),
+        ] {
+            let sync_status = parse_sync_status(&format!(
+                r#"{{
+                    "head_slot": "1",
+                    "sync_distance": "10",
+                    "is_syncing": {is_syncing},
+                    "is_optimistic": {is_optimistic},
+                    "el_offline": {el_offline}
+                }}"#
+            ));
+
+            assert!(!sync_status.is_synced());
         }
     }
 }
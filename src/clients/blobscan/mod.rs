@@ -1,13 +1,15 @@
 use backoff::ExponentialBackoff;
+use ethers::types::H256;
 use reqwest::{Client, Url};
 
-use crate::{clients::common::ClientResult, json_get, json_put};
+use crate::{clients::common::ClientResult, json_delete, json_get, json_post, json_put};
 
 use self::{
     jwt_manager::{Config as JWTManagerConfig, JWTManager},
     types::{
-        Blob, Block, BlockchainSyncState, BlockchainSyncStateRequest, BlockchainSyncStateResponse,
-        IndexRequest, ReorgedSlotRequest, Transaction,
+        BlockchainSyncState, BlockchainSyncStateRequest, BlockchainSyncStateResponse,
+        FailedSlotsChunk, FailedSlotsChunksRequest, FailedSlotsChunksResponse, IndexBatchRequest,
+        IndexRequest, RemoveFailedSlotsChunksRequest, ReorgedBlock, ReorgedBlocksRequest,
     },
 };
 
@@ -46,33 +48,68 @@ impl BlobscanClient {
         })
     }
 
-    pub async fn index(
-        &self,
-        block: Block,
-        transactions: Vec<Transaction>,
-        blobs: Vec<Blob>,
-    ) -> ClientResult<()> {
+    pub async fn index(&self, req: &IndexRequest) -> ClientResult<()> {
         let url = self.base_url.join("indexer/block-txs-blobs")?;
         let token = self.jwt_manager.get_token()?;
-        let req = IndexRequest {
-            block,
-            transactions,
-            blobs,
-        };
+
+        json_put!(&self.client, url, token, req).map(|_: Option<()>| ())
+    }
+
+    /// Indexes several blocks along with their transactions and blobs in a single request.
+    pub async fn index_batch(&self, entries: &[IndexRequest]) -> ClientResult<()> {
+        let url = self.base_url.join("indexer/block-txs-blobs/batch")?;
+        let token = self.jwt_manager.get_token()?;
+        let req = IndexBatchRequest { entries };
 
         json_put!(&self.client, url, token, &req).map(|_: Option<()>| ())
     }
 
-    pub async fn handle_reorged_slot(&self, slot: u32) -> ClientResult<()> {
-        let url = self.base_url.join("indexer/reorged-slot")?;
+    pub async fn handle_reorged_blocks(
+        &self,
+        new_head_block_root: H256,
+        reorged_blocks: Vec<ReorgedBlock>,
+    ) -> ClientResult<()> {
+        let url = self.base_url.join("indexer/reorged-blocks")?;
         let token = self.jwt_manager.get_token()?;
-        let req = ReorgedSlotRequest {
-            new_head_slot: slot,
+        let req = ReorgedBlocksRequest {
+            new_head_block_root,
+            reorged_blocks,
         };
 
         json_put!(&self.client, url, token, &req).map(|_: Option<()>| ())
     }
 
+    pub async fn add_failed_slots_chunks(&self, chunks: Vec<FailedSlotsChunk>) -> ClientResult<()> {
+        let url = self.base_url.join("indexer/failed-slots-chunks")?;
+        let token = self.jwt_manager.get_token()?;
+        let req = FailedSlotsChunksRequest { chunks };
+
+        json_post!(&self.client, url, token, &req).map(|_: Option<()>| ())
+    }
+
+    pub async fn get_failed_slots_chunks(&self) -> ClientResult<Option<Vec<FailedSlotsChunk>>> {
+        let url = self.base_url.join("indexer/failed-slots-chunks")?;
+        let token = self.jwt_manager.get_token()?;
+
+        // Not retried, so the store can fall back to the local file right away
+        json_get!(
+            &self.client,
+            url,
+            FailedSlotsChunksResponse,
+            token,
+            None::<ExponentialBackoff>
+        )
+        .map(|res: Option<FailedSlotsChunksResponse>| res.map(|r| r.chunks))
+    }
+
+    pub async fn remove_failed_slots_chunks(&self, chunk_ids: Vec<u32>) -> ClientResult<()> {
+        let url = self.base_url.join("indexer/failed-slots-chunks")?;
+        let token = self.jwt_manager.get_token()?;
+        let req = RemoveFailedSlotsChunksRequest { chunk_ids };
+
+        json_delete!(&self.client, url, token, &req).map(|_: Option<()>| ())
+    }
+
     pub async fn update_sync_state(&self, sync_state: BlockchainSyncState) -> ClientResult<()> {
         let url = self.base_url.join("blockchain-sync-state")?;
         let token = self.jwt_manager.get_token()?;
@@ -41,7 +41,7 @@ pub struct Blob {
     pub index: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FailedSlotsChunk {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -50,6 +50,22 @@ pub struct FailedSlotsChunk {
     pub final_slot: u32,
 }
 
+#[derive(Serialize, Debug)]
+pub struct FailedSlotsChunksRequest {
+    pub chunks: Vec<FailedSlotsChunk>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FailedSlotsChunksResponse {
+    pub chunks: Vec<FailedSlotsChunk>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveFailedSlotsChunksRequest {
+    pub chunk_ids: Vec<u32>,
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct BlockchainSyncStateRequest {
@@ -57,6 +73,8 @@ pub struct BlockchainSyncStateRequest {
     pub last_lower_synced_slot: Option<u32>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_upper_synced_slot: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_finalized_slot: Option<u32>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -66,12 +84,15 @@ pub struct BlockchainSyncStateResponse {
     pub last_lower_synced_slot: Option<u32>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_upper_synced_slot: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_finalized_slot: Option<u32>,
 }
 
 #[derive(Debug)]
 pub struct BlockchainSyncState {
     pub last_lower_synced_slot: Option<u32>,
     pub last_upper_synced_slot: Option<u32>,
+    pub last_finalized_slot: Option<u32>,
 }
 
 #[derive(Serialize, Debug)]
@@ -81,10 +102,25 @@ pub struct IndexRequest {
     pub blobs: Vec<Blob>,
 }
 
+#[derive(Serialize, Debug)]
+pub struct IndexBatchRequest<'a> {
+    pub entries: &'a [IndexRequest],
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReorgedBlock {
+    pub slot: u32,
+    pub block_root: H256,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_block_hash: Option<H256>,
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
-pub struct ReorgedSlotRequest {
-    pub new_head_slot: u32,
+pub struct ReorgedBlocksRequest {
+    pub new_head_block_root: H256,
+    pub reorged_blocks: Vec<ReorgedBlock>,
 }
 
 impl fmt::Debug for Blob {
@@ -234,6 +270,7 @@ impl From<BlockchainSyncStateResponse> for BlockchainSyncState {
         Self {
             last_lower_synced_slot: response.last_lower_synced_slot,
             last_upper_synced_slot: response.last_upper_synced_slot,
+            last_finalized_slot: response.last_finalized_slot,
         }
     }
 }
@@ -243,6 +280,7 @@ impl From<BlockchainSyncState> for BlockchainSyncStateRequest {
         Self {
             last_lower_synced_slot: sync_state.last_lower_synced_slot,
             last_upper_synced_slot: sync_state.last_upper_synced_slot,
+            last_finalized_slot: sync_state.last_finalized_slot,
         }
     }
 }
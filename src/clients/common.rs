@@ -41,6 +41,24 @@ pub enum ClientError {
     SerdeError(#[from] serde_json::Error),
 }
 
+impl ClientError {
+    /// True if the error may go away by retrying the request, like transport errors, timeouts
+    /// or server errors. Client errors and undecodable responses are returned as they are.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Reqwest(error) => match error.status() {
+                Some(status) => status.is_server_error(),
+                None => error.is_connect() || error.is_timeout() || error.is_request(),
+            },
+            Self::ApiError(ErrorResponse {
+                code: NumericOrTextCode::Number(code),
+                ..
+            }) => (500..600).contains(code),
+            _ => false,
+        }
+    }
+}
+
 /// API Response
 #[derive(Deserialize, Debug, Clone)]
 #[serde(untagged)]
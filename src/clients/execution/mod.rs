@@ -0,0 +1,467 @@
+use anyhow::{anyhow, Context as AnyhowContext, Result};
+use ethers::prelude::*;
+use futures::future::join_all;
+use tracing::warn;
+
+use crate::utils::web3::get_tx_versioned_hashes;
+
+/// Pool of execution nodes. Requests fall back to the next node when one fails or doesn't have
+/// the requested data. In quorum mode, blocks are only accepted once enough nodes agree on them.
+#[derive(Debug)]
+pub struct ProviderPool {
+    providers: Vec<(String, Provider<Http>)>,
+    quorum: Option<usize>,
+}
+
+pub struct Config {
+    pub endpoints: Vec<String>,
+    /// Amount of nodes that need to agree on a block's hash and blob transactions.
+    pub quorum: Option<usize>,
+}
+
+/// Parts of a block the nodes need to agree on: its hash and the versioned hashes of its blob
+/// transactions.
+type BlockFingerprint = (H256, Vec<(H256, Vec<H256>)>);
+
+impl ProviderPool {
+    pub fn try_new(config: Config) -> Result<Self> {
+        if config.endpoints.is_empty() {
+            return Err(anyhow!("No execution node endpoint provided"));
+        }
+
+        if let Some(quorum) = config.quorum {
+            if quorum == 0 || quorum > config.endpoints.len() {
+                return Err(anyhow!(
+                    "Execution nodes quorum must be between 1 and the {} endpoints provided",
+                    config.endpoints.len()
+                ));
+            }
+        }
+
+        let providers = config
+            .endpoints
+            .into_iter()
+            .map(|endpoint| {
+                let provider = Provider::<Http>::try_from(endpoint.as_str())
+                    .with_context(|| format!("Invalid execution node endpoint {endpoint}"))?;
+
+                Ok((endpoint, provider))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            providers,
+            quorum: config.quorum,
+        })
+    }
+
+    pub async fn get_block_with_txs(
+        &self,
+        block_hash: H256,
+    ) -> Result<Option<Block<Transaction>>, ProviderError> {
+        match self.quorum {
+            Some(quorum) => self._get_block_with_txs_by_quorum(block_hash, quorum).await,
+            None => self._get_block_with_txs_with_fallback(block_hash).await,
+        }
+    }
+
+    /// Fetches the block with the given number from the first node that has it. Unlike
+    /// blocks with transactions, it isn't checked against the quorum.
+    pub async fn get_block(&self, block_number: u64) -> Result<Option<Block<H256>>, ProviderError> {
+        let mut last_error = None;
+
+        for (endpoint, provider) in self.providers.iter() {
+            match provider.get_block(block_number).await {
+                Ok(Some(block)) => return Ok(Some(block)),
+                Ok(None) => {}
+                Err(error) => {
+                    warn!(
+                        target = "provider_pool",
+                        endpoint,
+                        block_number,
+                        ?error,
+                        "Execution node request failed. Falling back to the next one…"
+                    );
+
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        match last_error {
+            Some(error) => Err(error),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether enough nodes have finished syncing to serve blocks: all the ones needed for the
+    /// quorum or, otherwise, any of them. Nodes that can't be reached count as not synced.
+    pub async fn is_synced(&self) -> bool {
+        let results = join_all(
+            self.providers
+                .iter()
+                .map(|(_, provider)| provider.syncing()),
+        )
+        .await;
+        let mut synced_nodes = 0;
+
+        for ((endpoint, _), result) in self.providers.iter().zip(results) {
+            match result {
+                Ok(SyncingStatus::IsFalse) => synced_nodes += 1,
+                Ok(SyncingStatus::IsSyncing(progress)) => {
+                    warn!(
+                        target = "provider_pool",
+                        endpoint,
+                        current_block = %progress.current_block,
+                        highest_block = %progress.highest_block,
+                        "Execution node is syncing"
+                    );
+                }
+                Err(error) => {
+                    warn!(
+                        target = "provider_pool",
+                        endpoint,
+                        ?error,
+                        "Failed to fetch execution node sync status"
+                    );
+                }
+            }
+        }
+
+        synced_nodes >= self.quorum.unwrap_or(1)
+    }
+
+    async fn _get_block_with_txs_with_fallback(
+        &self,
+        block_hash: H256,
+    ) -> Result<Option<Block<Transaction>>, ProviderError> {
+        let mut last_error = None;
+
+        for (endpoint, provider) in self.providers.iter() {
+            match provider.get_block_with_txs(block_hash).await {
+                Ok(Some(block)) => return Ok(Some(block)),
+                // The node may be lagging behind, so another one could have the block
+                Ok(None) => {
+                    warn!(
+                        target = "provider_pool",
+                        endpoint,
+                        ?block_hash,
+                        "Execution block not found on node. Falling back to the next one…"
+                    );
+                }
+                Err(error) => {
+                    warn!(
+                        target = "provider_pool",
+                        endpoint,
+                        ?block_hash,
+                        ?error,
+                        "Execution node request failed. Falling back to the next one…"
+                    );
+
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        match last_error {
+            Some(error) => Err(error),
+            None => Ok(None),
+        }
+    }
+
+    async fn _get_block_with_txs_by_quorum(
+        &self,
+        block_hash: H256,
+        quorum: usize,
+    ) -> Result<Option<Block<Transaction>>, ProviderError> {
+        let results = join_all(
+            self.providers
+                .iter()
+                .map(|(_, provider)| provider.get_block_with_txs(block_hash)),
+        )
+        .await;
+        let mut candidates: Vec<(BlockFingerprint, Block<Transaction>, usize)> = vec![];
+        let mut not_found_votes = 0;
+
+        for ((endpoint, _), result) in self.providers.iter().zip(results) {
+            let block = match result {
+                Ok(Some(block)) => block,
+                Ok(None) => {
+                    not_found_votes += 1;
+
+                    continue;
+                }
+                Err(error) => {
+                    warn!(
+                        target = "provider_pool",
+                        endpoint,
+                        ?block_hash,
+                        ?error,
+                        "Execution node request failed"
+                    );
+
+                    continue;
+                }
+            };
+            let fingerprint = match get_block_fingerprint(&block) {
+                Ok(fingerprint) => fingerprint,
+                Err(error) => {
+                    warn!(
+                        target = "provider_pool",
+                        endpoint,
+                        ?block_hash,
+                        ?error,
+                        "Invalid execution block returned by node"
+                    );
+
+                    continue;
+                }
+            };
+
+            match candidates
+                .iter_mut()
+                .find(|(candidate_fingerprint, _, _)| *candidate_fingerprint == fingerprint)
+            {
+                Some((_, _, votes)) => *votes += 1,
+                None => candidates.push((fingerprint, block, 1)),
+            }
+        }
+
+        if let Some(index) = candidates.iter().position(|(_, _, votes)| *votes >= quorum) {
+            return Ok(Some(candidates.swap_remove(index).1));
+        }
+
+        if not_found_votes >= quorum {
+            return Ok(None);
+        }
+
+        warn!(
+            target = "provider_pool",
+            ?block_hash,
+            quorum,
+            candidates = candidates.len(),
+            not_found_votes,
+            "Execution nodes didn't reach a quorum on block"
+        );
+
+        Err(ProviderError::CustomError(format!(
+            "Execution nodes didn't reach a quorum of {quorum} on block {block_hash:#x}"
+        )))
+    }
+}
+
+fn get_block_fingerprint(block: &Block<Transaction>) -> Result<BlockFingerprint> {
+    let block_hash = block.hash.context("Execution block is missing its hash")?;
+    let mut blob_txs = vec![];
+
+    for tx in block.transactions.iter() {
+        if let Some(versioned_hashes) = get_tx_versioned_hashes(tx)? {
+            blob_txs.push((tx.hash, versioned_hashes));
+        }
+    }
+
+    Ok((block_hash, blob_txs))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::utils::mock_server::start_execution_node;
+
+    use super::*;
+
+    fn create_block(block_hash: H256, versioned_hashes: &[H256]) -> Block<Transaction> {
+        let mut tx = serde_json::to_value(Transaction {
+            hash: H256::from_low_u64_be(1),
+            gas_price: Some(U256::one()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        tx["blobVersionedHashes"] = json!(versioned_hashes);
+
+        Block {
+            hash: Some(block_hash),
+            number: Some(U64::from(1)),
+            transactions: vec![serde_json::from_value(tx).unwrap()],
+            ..Default::default()
+        }
+    }
+
+    async fn create_provider_pool(
+        results: Vec<Option<Value>>,
+        quorum: Option<usize>,
+    ) -> ProviderPool {
+        let mut endpoints = vec![];
+
+        for result in results {
+            endpoints.push(start_execution_node(result).await);
+        }
+
+        ProviderPool::try_new(Config { endpoints, quorum }).unwrap()
+    }
+
+    fn block_hash() -> H256 {
+        H256::from_low_u64_be(100)
+    }
+
+    fn block_result(versioned_hashes: &[H256]) -> Option<Value> {
+        Some(serde_json::to_value(create_block(block_hash(), versioned_hashes)).unwrap())
+    }
+
+    #[test]
+    fn test_try_new_with_invalid_config() {
+        let endpoints = vec![
+            "http://localhost:8545".to_string(),
+            "http://localhost:8546".to_string(),
+        ];
+
+        assert!(ProviderPool::try_new(Config {
+            endpoints: vec![],
+            quorum: None
+        })
+        .is_err());
+
+        for quorum in [0, 3] {
+            assert!(ProviderPool::try_new(Config {
+                endpoints: endpoints.clone(),
+                quorum: Some(quorum),
+            })
+            .is_err());
+        }
+
+        assert!(ProviderPool::try_new(Config {
+            endpoints: vec!["not a url".to_string()],
+            quorum: None,
+        })
+        .is_err());
+        assert!(ProviderPool::try_new(Config {
+            endpoints,
+            quorum: Some(2),
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn test_block_fingerprint() {
+        let versioned_hash = H256::from_low_u64_be(2);
+        let block = create_block(block_hash(), &[versioned_hash]);
+
+        assert_eq!(
+            get_block_fingerprint(&block).unwrap(),
+            (
+                block_hash(),
+                vec![(H256::from_low_u64_be(1), vec![versioned_hash])]
+            )
+        );
+        assert!(get_block_fingerprint(&Block::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fall_back_to_next_node() {
+        let provider_pool =
+            create_provider_pool(vec![None, Some(Value::Null), block_result(&[])], None).await;
+        let block = provider_pool
+            .get_block_with_txs(block_hash())
+            .await
+            .unwrap();
+
+        assert_eq!(block.unwrap().hash, Some(block_hash()));
+    }
+
+    #[tokio::test]
+    async fn test_fall_back_returns_last_error() {
+        let provider_pool = create_provider_pool(vec![Some(Value::Null), None], None).await;
+
+        assert!(provider_pool
+            .get_block_with_txs(block_hash())
+            .await
+            .is_err());
+
+        let provider_pool =
+            create_provider_pool(vec![Some(Value::Null), Some(Value::Null)], None).await;
+
+        assert!(provider_pool
+            .get_block_with_txs(block_hash())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_quorum_reached() {
+        let versioned_hash = H256::from_low_u64_be(2);
+        let provider_pool = create_provider_pool(
+            vec![
+                block_result(&[H256::from_low_u64_be(3)]),
+                None,
+                block_result(&[versioned_hash]),
+                block_result(&[versioned_hash]),
+            ],
+            Some(2),
+        )
+        .await;
+        let block = provider_pool
+            .get_block_with_txs(block_hash())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            get_tx_versioned_hashes(&block.transactions[0]).unwrap(),
+            Some(vec![versioned_hash])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quorum_not_reached() {
+        let provider_pool = create_provider_pool(
+            vec![
+                block_result(&[H256::from_low_u64_be(2)]),
+                block_result(&[H256::from_low_u64_be(3)]),
+                Some(Value::Null),
+            ],
+            Some(2),
+        )
+        .await;
+
+        assert!(provider_pool
+            .get_block_with_txs(block_hash())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_quorum_on_missing_block() {
+        let provider_pool = create_provider_pool(
+            vec![Some(Value::Null), block_result(&[]), Some(Value::Null)],
+            Some(2),
+        )
+        .await;
+
+        assert!(provider_pool
+            .get_block_with_txs(block_hash())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_is_synced() {
+        let syncing = json!({
+            "startingBlock": "0x0",
+            "currentBlock": "0x1",
+            "highestBlock": "0x2"
+        });
+        let provider_pool =
+            create_provider_pool(vec![Some(syncing.clone()), Some(json!(false))], None).await;
+
+        assert!(provider_pool.is_synced().await);
+
+        let provider_pool =
+            create_provider_pool(vec![Some(json!(false)), Some(syncing), None], Some(2)).await;
+
+        assert!(!provider_pool.is_synced().await);
+    }
+}
@@ -16,9 +16,9 @@ macro_rules! json_get {
           req = req.bearer_auth($auth_token);
         }
 
-        let resp = if $exp_backoff.is_some() {
+        let resp = if let Some(exp_backoff) = $exp_backoff {
             match backoff::future::retry_notify(
-                $exp_backoff.unwrap(),
+                exp_backoff,
                 || {
                     let req = req.try_clone().unwrap();
 
@@ -92,25 +92,26 @@ macro_rules! json_get {
 }
 
 #[macro_export]
-/// Make a PUT request sending JSON.
+/// Make a request with the given method sending JSON.
 /// if JSON deser fails, emit a `WARN` level tracing event
-macro_rules! json_put {
-    ($client:expr, $url:expr, $auth_token:expr, $body:expr) => {{
+macro_rules! json_send {
+    ($client:expr, $method:ident, $url:expr, $auth_token:expr, $body:expr) => {{
+        let method = stringify!($method).to_uppercase();
         let url = $url.clone();
         let body = format!("{:?}", $body);
 
-        tracing::debug!(method = "PUT", url = url.as_str(), body, "Dispatching API client request");
+        tracing::debug!(method = method.as_str(), url = url.as_str(), body, "Dispatching API client request");
 
 
         let resp = match $client
-            .put($url)
+            .$method($url)
             .bearer_auth($auth_token)
             .json($body)
             .send()
             .await {
                 Err(error) => {
                     tracing::warn!(
-                        method = "PUT",
+                        method = method.as_str(),
                         url = %url,
                         body = body,
                         ?error,
@@ -127,7 +128,7 @@ macro_rules! json_put {
 
         if result.is_err() {
             tracing::warn!(
-                method = "PUT",
+                method = method.as_str(),
                 url = %url,
                 body,
                 response = text.as_str(),
@@ -138,3 +139,30 @@ macro_rules! json_put {
         result.into_client_result()
     }};
 }
+
+#[macro_export]
+/// Make a PUT request sending JSON.
+/// if JSON deser fails, emit a `WARN` level tracing event
+macro_rules! json_put {
+    ($client:expr, $url:expr, $auth_token:expr, $body:expr) => {
+        $crate::json_send!($client, put, $url, $auth_token, $body)
+    };
+}
+
+#[macro_export]
+/// Make a POST request sending JSON.
+/// if JSON deser fails, emit a `WARN` level tracing event
+macro_rules! json_post {
+    ($client:expr, $url:expr, $auth_token:expr, $body:expr) => {
+        $crate::json_send!($client, post, $url, $auth_token, $body)
+    };
+}
+
+#[macro_export]
+/// Make a DELETE request sending JSON.
+/// if JSON deser fails, emit a `WARN` level tracing event
+macro_rules! json_delete {
+    ($client:expr, $url:expr, $auth_token:expr, $body:expr) => {
+        $crate::json_send!($client, delete, $url, $auth_token, $body)
+    };
+}
@@ -3,3 +3,4 @@ mod macros;
 pub mod beacon;
 pub mod blobscan;
 pub mod common;
+pub mod execution;
@@ -1,27 +1,50 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 use anyhow::Result as AnyhowResult;
 use backoff::ExponentialBackoffBuilder;
-use ethers::prelude::*;
+use tokio::sync::watch;
 
 use crate::{
-    clients::beacon::{BeaconClient, Config as BeaconClientConfig},
+    chain_spec::ChainSpec,
+    clients::beacon::{
+        blob_source::{BeaconApiBlobSource, BlobSource, DirectoryBlobSource},
+        era::EraStore,
+        BeaconClient, Config as BeaconClientConfig,
+    },
     clients::blobscan::{BlobscanClient, Config as BlobscanClientConfig},
+    clients::execution::{Config as ProviderPoolConfig, ProviderPool},
     env::Environment,
+    failed_slots_chunks::{Config as FailedSlotsChunksStoreConfig, FailedSlotsChunksStore},
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 struct ContextRef {
     pub beacon_client: BeaconClient,
     pub blobscan_client: BlobscanClient,
-    pub provider: Provider<Http>,
+    pub failed_slots_chunks_store: FailedSlotsChunksStore,
+    pub provider: ProviderPool,
+    pub chain_spec: ChainSpec,
+    pub era_store: Option<EraStore>,
+    pub last_finalized_slot: RwLock<Option<u32>>,
+    pub verify_blob_kzg_proofs: bool,
+    pub nodes_synced_tx: watch::Sender<bool>,
+    pub nodes_synced_rx: watch::Receiver<bool>,
 }
 
 pub struct Config {
     pub blobscan_api_endpoint: String,
-    pub beacon_node_url: String,
-    pub execution_node_endpoint: String,
+    pub beacon_node_urls: Vec<String>,
+    pub execution_node_endpoints: Vec<String>,
+    pub execution_node_quorum: Option<usize>,
+    pub blob_archive_endpoint: Option<String>,
+    pub blob_archive_dir: Option<String>,
+    pub era_files_dir: Option<String>,
     pub secret_key: String,
+    pub failed_slots_chunks_file: String,
+    pub verify_blob_kzg_proofs: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -30,37 +53,84 @@ pub struct Context {
 }
 
 impl Context {
-    pub fn try_new(config: Config) -> AnyhowResult<Self> {
+    pub async fn try_new(config: Config) -> AnyhowResult<Self> {
         let Config {
             blobscan_api_endpoint,
-            beacon_node_url,
-            execution_node_endpoint,
+            beacon_node_urls,
+            execution_node_endpoints,
+            execution_node_quorum,
+            blob_archive_endpoint,
+            blob_archive_dir,
+            era_files_dir,
             secret_key,
+            failed_slots_chunks_file,
+            verify_blob_kzg_proofs,
         } = config;
         let exp_backoff = Some(ExponentialBackoffBuilder::default().build());
+        // Nodes are considered out of sync until proven otherwise
+        let (nodes_synced_tx, nodes_synced_rx) = watch::channel(false);
 
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(8))
             .build()?;
 
+        let blobscan_client = BlobscanClient::try_with_client(
+            client.clone(),
+            BlobscanClientConfig {
+                base_url: blobscan_api_endpoint,
+                secret_key,
+                exp_backoff: exp_backoff.clone(),
+            },
+        )?;
+
+        // The local archive is checked first as it's the cheapest to query
+        let mut blob_sources: Vec<Box<dyn BlobSource>> = vec![];
+
+        if let Some(blob_archive_dir) = blob_archive_dir {
+            blob_sources.push(Box::new(DirectoryBlobSource::new(blob_archive_dir)));
+        }
+
+        if let Some(blob_archive_endpoint) = blob_archive_endpoint {
+            blob_sources.push(Box::new(BeaconApiBlobSource::try_with_client(
+                client.clone(),
+                &blob_archive_endpoint,
+                exp_backoff.clone(),
+            )?));
+        }
+
+        let beacon_client = BeaconClient::try_with_client(
+            client,
+            BeaconClientConfig {
+                base_urls: beacon_node_urls,
+                blob_sources,
+                exp_backoff,
+            },
+        )?;
+        let chain_spec = ChainSpec::fetch(&beacon_client).await?;
+        let era_store = era_files_dir
+            .map(|era_files_dir| EraStore::try_new(era_files_dir, chain_spec.clone()))
+            .transpose()?;
+
         Ok(Self {
             inner: Arc::new(ContextRef {
-                blobscan_client: BlobscanClient::try_with_client(
-                    client.clone(),
-                    BlobscanClientConfig {
-                        base_url: blobscan_api_endpoint,
-                        secret_key,
-                        exp_backoff: exp_backoff.clone(),
+                failed_slots_chunks_store: FailedSlotsChunksStore::new(
+                    blobscan_client.clone(),
+                    FailedSlotsChunksStoreConfig {
+                        file_path: failed_slots_chunks_file,
                     },
-                )?,
-                beacon_client: BeaconClient::try_with_client(
-                    client,
-                    BeaconClientConfig {
-                        base_url: beacon_node_url,
-                        exp_backoff,
-                    },
-                )?,
-                provider: Provider::<Http>::try_from(execution_node_endpoint)?,
+                ),
+                blobscan_client,
+                beacon_client,
+                provider: ProviderPool::try_new(ProviderPoolConfig {
+                    endpoints: execution_node_endpoints,
+                    quorum: execution_node_quorum,
+                })?,
+                chain_spec,
+                era_store,
+                last_finalized_slot: RwLock::new(None),
+                verify_blob_kzg_proofs,
+                nodes_synced_tx,
+                nodes_synced_rx,
             }),
         })
     }
@@ -73,18 +143,197 @@ impl Context {
         &self.inner.blobscan_client
     }
 
-    pub fn provider(&self) -> &Provider<Http> {
+    pub fn failed_slots_chunks_store(&self) -> &FailedSlotsChunksStore {
+        &self.inner.failed_slots_chunks_store
+    }
+
+    pub fn provider(&self) -> &ProviderPool {
         &self.inner.provider
     }
+
+    pub fn chain_spec(&self) -> &ChainSpec {
+        &self.inner.chain_spec
+    }
+
+    /// Local Era files blocks are read from, if configured.
+    pub fn era_store(&self) -> Option<&EraStore> {
+        self.inner.era_store.as_ref()
+    }
+
+    /// Whether blobs are checked against their KZG commitments before being indexed.
+    pub fn verify_blob_kzg_proofs(&self) -> bool {
+        self.inner.verify_blob_kzg_proofs
+    }
+
+    /// Whether both the beacon and execution nodes are synced and can be indexed from.
+    pub fn are_nodes_synced(&self) -> bool {
+        *self.inner.nodes_synced_rx.borrow()
+    }
+
+    pub fn set_nodes_synced(&self, synced: bool) {
+        if self.are_nodes_synced() != synced {
+            // A receiver is kept in the context, so sending can't fail
+            let _ = self.inner.nodes_synced_tx.send(synced);
+        }
+    }
+
+    /// Waits until the nodes are synced.
+    pub async fn wait_for_nodes_synced(&self) {
+        let mut nodes_synced_rx = self.inner.nodes_synced_rx.clone();
+
+        while !*nodes_synced_rx.borrow_and_update() {
+            if nodes_synced_rx.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    pub fn last_finalized_slot(&self) -> Option<u32> {
+        *self.inner.last_finalized_slot.read().unwrap()
+    }
+
+    pub fn set_last_finalized_slot(&self, slot: u32) {
+        let mut last_finalized_slot = self.inner.last_finalized_slot.write().unwrap();
+
+        // Finality never goes backwards
+        if last_finalized_slot.is_none_or(|last_slot| slot > last_slot) {
+            *last_finalized_slot = Some(slot);
+        }
+    }
+
+    /// Whether the given slot is at or below the latest finalized slot and therefore can't be reorged.
+    pub fn is_slot_finalized(&self, slot: u32) -> bool {
+        self.last_finalized_slot()
+            .is_some_and(|finalized_slot| slot <= finalized_slot)
+    }
+}
+
+#[cfg(test)]
+impl Context {
+    /// Context on mainnet relying on the given beacon node, whose Blobscan API and execution
+    /// node can't be reached.
+    pub fn for_beacon_node(beacon_node_url: &str) -> Self {
+        Self::for_nodes(beacon_node_url, "http://127.0.0.1:1", "http://127.0.0.1:1")
+    }
+
+    /// Context on mainnet relying on the given nodes and Blobscan API. Each context keeps its
+    /// failed slots chunks in a file of its own.
+    pub fn for_nodes(
+        beacon_node_url: &str,
+        execution_node_url: &str,
+        blobscan_api_url: &str,
+    ) -> Self {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static CONTEXTS: AtomicUsize = AtomicUsize::new(0);
+
+        let client = reqwest::Client::new();
+        let failed_slots_chunks_file = std::env::temp_dir().join(format!(
+            "blob-indexer-{}-context-{}-failed-slots-chunks.json",
+            std::process::id(),
+            CONTEXTS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let (nodes_synced_tx, nodes_synced_rx) = watch::channel(true);
+        let blobscan_client = BlobscanClient::try_with_client(
+            client.clone(),
+            BlobscanClientConfig {
+                base_url: blobscan_api_url.to_string(),
+                secret_key: "secret".to_string(),
+                exp_backoff: None,
+            },
+        )
+        .unwrap();
+
+        Self {
+            inner: Arc::new(ContextRef {
+                beacon_client: BeaconClient::try_with_client(
+                    client,
+                    BeaconClientConfig {
+                        base_urls: vec![beacon_node_url.to_string()],
+                        blob_sources: vec![],
+                        exp_backoff: None,
+                    },
+                )
+                .unwrap(),
+                failed_slots_chunks_store: FailedSlotsChunksStore::new(
+                    blobscan_client.clone(),
+                    FailedSlotsChunksStoreConfig {
+                        file_path: failed_slots_chunks_file.display().to_string(),
+                    },
+                ),
+                blobscan_client,
+                provider: ProviderPool::try_new(ProviderPoolConfig {
+                    endpoints: vec![execution_node_url.to_string()],
+                    quorum: None,
+                })
+                .unwrap(),
+                chain_spec: ChainSpec::mainnet(),
+                era_store: None,
+                last_finalized_slot: RwLock::new(None),
+                verify_blob_kzg_proofs: false,
+                nodes_synced_tx,
+                nodes_synced_rx,
+            }),
+        }
+    }
 }
 
 impl From<&Environment> for Config {
     fn from(env: &Environment) -> Self {
         Self {
             blobscan_api_endpoint: env.blobscan_api_endpoint.clone(),
-            beacon_node_url: env.beacon_node_endpoint.clone(),
-            execution_node_endpoint: env.execution_node_endpoint.clone(),
+            beacon_node_urls: env
+                .beacon_node_endpoint
+                .split(',')
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+                .collect(),
+            execution_node_endpoints: env
+                .execution_node_endpoint
+                .split(',')
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+                .collect(),
+            execution_node_quorum: env.execution_node_quorum,
+            blob_archive_endpoint: env.blob_archive_endpoint.clone(),
+            blob_archive_dir: env.blob_archive_dir.clone(),
+            era_files_dir: env.era_files_dir.clone(),
             secret_key: env.secret_key.clone(),
+            failed_slots_chunks_file: env.failed_slots_chunks_file.clone(),
+            verify_blob_kzg_proofs: env.verify_blob_kzg_proofs,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_finalized_slot() {
+        let context = Context::for_beacon_node("http://127.0.0.1:1");
+
+        assert_eq!(context.last_finalized_slot(), None);
+        assert!(!context.is_slot_finalized(0));
+
+        context.set_last_finalized_slot(100);
+
+        assert!(context.is_slot_finalized(99));
+        assert!(context.is_slot_finalized(100));
+        assert!(!context.is_slot_finalized(101));
+    }
+
+    #[test]
+    fn test_last_finalized_slot_never_goes_backwards() {
+        let context = Context::for_beacon_node("http://127.0.0.1:1");
+
+        context.set_last_finalized_slot(100);
+        context.set_last_finalized_slot(64);
+
+        assert_eq!(context.last_finalized_slot(), Some(100));
+
+        context.set_last_finalized_slot(132);
+
+        assert_eq!(context.last_finalized_slot(), Some(132));
+    }
+}
@@ -9,8 +9,16 @@ pub struct Environment {
     pub beacon_node_endpoint: String,
     #[serde(default = "default_execution_node_endpoint")]
     pub execution_node_endpoint: String,
+    pub execution_node_quorum: Option<usize>,
+    pub blob_archive_endpoint: Option<String>,
+    pub blob_archive_dir: Option<String>,
+    pub era_files_dir: Option<String>,
     pub secret_key: String,
+    #[serde(default = "default_failed_slots_chunks_file")]
+    pub failed_slots_chunks_file: String,
     pub lowest_indexed_slot: Option<u32>,
+    #[serde(default = "default_verify_blob_kzg_proofs")]
+    pub verify_blob_kzg_proofs: bool,
     pub sentry_dsn: Option<String>,
 }
 
@@ -26,6 +34,14 @@ fn default_execution_node_endpoint() -> String {
     "http://localhost:8545".to_string()
 }
 
+fn default_failed_slots_chunks_file() -> String {
+    "failed_slots_chunks.json".to_string()
+}
+
+fn default_verify_blob_kzg_proofs() -> bool {
+    true
+}
+
 impl Environment {
     pub fn from_env() -> Result<Self, envy::Error> {
         match envy::from_env::<Environment>() {
@@ -0,0 +1,14 @@
+use crate::clients::common::ClientError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FailedSlotsChunksStoreError {
+    #[error(transparent)]
+    ClientError(#[from] ClientError),
+    #[error("Failed to access failed slots chunks file {path}: {error}")]
+    FileAccess { path: String, error: std::io::Error },
+    #[error("Failed to parse failed slots chunks file {path}: {error}")]
+    FileParsing {
+        path: String,
+        error: serde_json::Error,
+    },
+}
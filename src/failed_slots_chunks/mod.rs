@@ -0,0 +1,240 @@
+use std::{io::ErrorKind, path::PathBuf, sync::Arc};
+
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::clients::blobscan::{types::FailedSlotsChunk, BlobscanClient};
+
+use self::error::FailedSlotsChunksStoreError;
+
+pub mod error;
+
+/// Keeps track of the slots chunks that couldn't be indexed so they can be retried later.
+///
+/// Chunks are stored through the Blobscan API. When it can't be reached, they are
+/// stored in a local file instead. Chunks coming from the local file have no `id`.
+#[derive(Debug, Clone)]
+pub struct FailedSlotsChunksStore {
+    blobscan_client: BlobscanClient,
+    file_path: PathBuf,
+    // Serializes the read-modify-write cycles on the local file
+    file_lock: Arc<Mutex<()>>,
+}
+
+pub struct Config {
+    pub file_path: String,
+}
+
+impl FailedSlotsChunksStore {
+    pub fn new(blobscan_client: BlobscanClient, config: Config) -> Self {
+        Self {
+            blobscan_client,
+            file_path: PathBuf::from(config.file_path),
+            file_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub async fn add(
+        &self,
+        chunks: Vec<FailedSlotsChunk>,
+    ) -> Result<(), FailedSlotsChunksStoreError> {
+        if let Err(error) = self
+            .blobscan_client
+            .add_failed_slots_chunks(chunks.clone())
+            .await
+        {
+            warn!(
+                target = "failed_slots_chunks",
+                ?error,
+                file = %self.file_path.display(),
+                "Failed to save failed slots chunks through the API. Saving them locally…"
+            );
+
+            let _guard = self.file_lock.lock().await;
+            let mut local_chunks = self._read_file().await?;
+
+            local_chunks.extend(
+                chunks
+                    .into_iter()
+                    .map(|chunk| FailedSlotsChunk { id: None, ..chunk }),
+            );
+
+            self._write_file(&local_chunks).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_all(&self) -> Result<Vec<FailedSlotsChunk>, FailedSlotsChunksStoreError> {
+        let mut chunks = match self.blobscan_client.get_failed_slots_chunks().await {
+            Ok(chunks) => chunks.unwrap_or_default(),
+            Err(error) => {
+                warn!(
+                    target = "failed_slots_chunks",
+                    ?error,
+                    "Failed to fetch failed slots chunks from the API"
+                );
+
+                vec![]
+            }
+        };
+
+        let _guard = self.file_lock.lock().await;
+
+        chunks.extend(self._read_file().await?);
+
+        Ok(chunks)
+    }
+
+    pub async fn remove(
+        &self,
+        chunk: &FailedSlotsChunk,
+    ) -> Result<(), FailedSlotsChunksStoreError> {
+        match chunk.id {
+            Some(id) => {
+                self.blobscan_client
+                    .remove_failed_slots_chunks(vec![id])
+                    .await?
+            }
+            None => {
+                let _guard = self.file_lock.lock().await;
+                let mut local_chunks = self._read_file().await?;
+
+                local_chunks.retain(|local_chunk| local_chunk != chunk);
+
+                self._write_file(&local_chunks).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn _read_file(&self) -> Result<Vec<FailedSlotsChunk>, FailedSlotsChunksStoreError> {
+        let content = match tokio::fs::read_to_string(&self.file_path).await {
+            Ok(content) => content,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(error) => {
+                return Err(FailedSlotsChunksStoreError::FileAccess {
+                    path: self.file_path.display().to_string(),
+                    error,
+                })
+            }
+        };
+
+        serde_json::from_str(&content).map_err(|error| FailedSlotsChunksStoreError::FileParsing {
+            path: self.file_path.display().to_string(),
+            error,
+        })
+    }
+
+    async fn _write_file(
+        &self,
+        chunks: &[FailedSlotsChunk],
+    ) -> Result<(), FailedSlotsChunksStoreError> {
+        let content = serde_json::to_string_pretty(chunks).map_err(|error| {
+            FailedSlotsChunksStoreError::FileParsing {
+                path: self.file_path.display().to_string(),
+                error,
+            }
+        })?;
+
+        tokio::fs::write(&self.file_path, content)
+            .await
+            .map_err(|error| FailedSlotsChunksStoreError::FileAccess {
+                path: self.file_path.display().to_string(),
+                error,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Client;
+
+    use crate::clients::blobscan::Config as BlobscanClientConfig;
+
+    use super::*;
+
+    /// Creates a store whose Blobscan API can't be reached, so chunks are kept in a local file.
+    fn create_store(name: &str) -> FailedSlotsChunksStore {
+        let blobscan_client = BlobscanClient::try_with_client(
+            Client::new(),
+            BlobscanClientConfig {
+                base_url: "http://127.0.0.1:1".to_string(),
+                secret_key: "secret".to_string(),
+                exp_backoff: None,
+            },
+        )
+        .unwrap();
+        let file_path = std::env::temp_dir().join(format!(
+            "blob-indexer-{}-{name}-failed-slots-chunks.json",
+            std::process::id()
+        ));
+
+        let _ = std::fs::remove_file(&file_path);
+
+        FailedSlotsChunksStore::new(
+            blobscan_client,
+            Config {
+                file_path: file_path.display().to_string(),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_store_chunks_locally_when_api_is_unreachable() {
+        let store = create_store("add");
+
+        assert!(store.get_all().await.unwrap().is_empty());
+
+        store
+            .add(vec![
+                (1, 10).into(),
+                FailedSlotsChunk {
+                    id: Some(3),
+                    initial_slot: 20,
+                    final_slot: 30,
+                },
+            ])
+            .await
+            .unwrap();
+        store.add(vec![(40, 50).into()]).await.unwrap();
+
+        // Chunks stored locally lose the id given by the API
+        assert_eq!(
+            store.get_all().await.unwrap(),
+            vec![(1, 10).into(), (20, 30).into(), (40, 50).into()]
+        );
+
+        let _ = std::fs::remove_file(&store.file_path);
+    }
+
+    #[tokio::test]
+    async fn test_remove_local_chunk() {
+        let store = create_store("remove");
+
+        store
+            .add(vec![(1, 10).into(), (20, 30).into()])
+            .await
+            .unwrap();
+        store.remove(&(1, 10).into()).await.unwrap();
+
+        assert_eq!(store.get_all().await.unwrap(), vec![(20, 30).into()]);
+
+        let _ = std::fs::remove_file(&store.file_path);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_file() {
+        let store = create_store("invalid");
+
+        std::fs::write(&store.file_path, "not json").unwrap();
+
+        assert!(matches!(
+            store.get_all().await,
+            Err(FailedSlotsChunksStoreError::FileParsing { .. })
+        ));
+
+        let _ = std::fs::remove_file(&store.file_path);
+    }
+}
@@ -2,8 +2,6 @@ use crate::{clients::common::ClientError, synchronizer::error::SynchronizerError
 
 #[derive(Debug, thiserror::Error)]
 pub enum IndexerError {
-    #[error(transparent)]
-    ReqwestEventSourceError(#[from] reqwest_eventsource::Error),
     #[error(transparent)]
     ClientError(#[from] ClientError),
     #[error(transparent)]
@@ -1,40 +1,66 @@
-use std::thread;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
 
-use anyhow::anyhow;
-use futures::StreamExt;
+use anyhow::{anyhow, Context as AnyhowContext};
+use backoff::{backoff::Backoff, ExponentialBackoffBuilder};
+use futures::{future::join_all, StreamExt};
 use reqwest_eventsource::Event;
 use tokio::{sync::mpsc, task::JoinHandle};
-use tracing::{debug, error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
 
 use crate::{
     args::Args,
     clients::{
-        beacon::types::{BlockId, HeadBlockEventData, Topic},
+        beacon::types::{
+            BlockId, ChainReorgEventData, FinalizedCheckpointEventData, HeadBlockEventData, Topic,
+        },
         blobscan::types::BlockchainSyncState,
     },
     context::{Config as ContextConfig, Context},
     env::Environment,
+    slots_processor::{index_buffer::IndexBatchConfig, SlotsProcessor},
     synchronizer::{Synchronizer, SynchronizerBuilder},
 };
 
 use self::{
     error::{IndexerError, IndexingTaskError},
-    types::{IndexerResult, IndexerTaskResult},
+    types::{IndexerResult, IndexerTaskResult, RangeSyncSummary},
 };
 
 pub mod error;
 pub mod types;
 
+const FAILED_SLOTS_CHUNKS_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+const FAILED_SLOTS_CHUNKS_MAX_RETRY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const EVENTS_MAX_RECONNECTION_INTERVAL: Duration = Duration::from_secs(60);
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+const BEACON_NODES_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(12);
+const NODES_SYNC_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Blocks synced while backfilling are sent to Blobscan in batches, as no one is waiting on them.
+const HISTORICAL_INDEX_BATCH_CONFIG: IndexBatchConfig = IndexBatchConfig {
+    max_blocks: 20,
+    max_size: 8 * 1024 * 1024,
+    max_delay: Duration::from_secs(10),
+};
+
 pub struct Indexer {
     context: Context,
+    cancellation_token: CancellationToken,
     lowest_indexed_slot: u32,
     num_threads: u32,
     slots_checkpoint: Option<u32>,
 }
 
 impl Indexer {
-    pub fn try_new(env: &Environment, args: &Args) -> IndexerResult<Self> {
-        let context = match Context::try_new(ContextConfig::from(env)) {
+    pub async fn try_new(
+        env: &Environment,
+        args: &Args,
+        cancellation_token: CancellationToken,
+    ) -> IndexerResult<Self> {
+        let context = match Context::try_new(ContextConfig::from(env)).await {
             Ok(c) => c,
             Err(error) => {
                 error!(target = "indexer", ?error, "Failed to create context");
@@ -48,16 +74,80 @@ impl Indexer {
                 .map_err(|err| anyhow!("Failed to get number of available threads: {:?}", err))?
                 .get() as u32,
         };
-        let lowest_indexed_slot = env.lowest_indexed_slot.unwrap_or(0);
+        let chain_spec = context.chain_spec();
+
+        info!(
+            target = "indexer",
+            seconds_per_slot = chain_spec.seconds_per_slot,
+            slots_per_epoch = chain_spec.slots_per_epoch,
+            genesis_time = chain_spec.genesis_time,
+            deneb_fork_slot = ?chain_spec.deneb_fork_slot(),
+            "Chain spec fetched from beacon node"
+        );
+
+        let blobs_retention_start_slot = chain_spec.blobs_retention_start_slot();
+        // There are no blobs to index before Deneb, nor to get once pruned unless a blob source
+        // keeps them
+        let lowest_indexed_slot = env.lowest_indexed_slot.unwrap_or_else(|| {
+            let deneb_fork_slot = chain_spec.deneb_fork_slot().unwrap_or(0);
+
+            if context.beacon_client().has_blob_sources() {
+                deneb_fork_slot
+            } else {
+                deneb_fork_slot.max(blobs_retention_start_slot)
+            }
+        });
+
+        if lowest_indexed_slot < blobs_retention_start_slot {
+            warn!(
+                target = "indexer",
+                lowest_indexed_slot,
+                blobs_retention_start_slot,
+                "Lowest indexed slot is outside the blobs retention window. Blobs of older slots may have been pruned by the beacon node"
+            );
+        }
 
         Ok(Self {
             context,
+            cancellation_token,
             num_threads,
             slots_checkpoint: args.slots_per_save,
             lowest_indexed_slot,
         })
     }
 
+    /// Resolves the execution block number, timestamp or date given to start indexing from
+    /// into the slot they fall into.
+    pub async fn resolve_start_slot(&self, args: &Args) -> IndexerResult<Option<u32>> {
+        let chain_spec = self.context.chain_spec();
+
+        let timestamp = if let Some(block_number) = args.from_block {
+            let block = self
+                .context
+                .provider()
+                .get_block(block_number)
+                .await
+                .map_err(|error| anyhow!(error))?
+                .with_context(|| format!("Execution block {block_number} not found"))?;
+
+            // Post-merge, execution blocks share the timestamp of the slot they were proposed at
+            block.timestamp.as_u64()
+        } else if let Some(timestamp) = args.from_timestamp {
+            timestamp
+        } else if let Some(date) = args.from_date {
+            u64::try_from(date.timestamp())
+                .map_err(|_| anyhow!("Date {date} is prior to the Unix epoch"))?
+        } else {
+            return Ok(None);
+        };
+
+        let slot = chain_spec.timestamp_to_slot(timestamp);
+
+        info!(target = "indexer", timestamp, slot, "Resolved start slot");
+
+        Ok(Some(slot))
+    }
+
     pub async fn run(&mut self, custom_start_block_id: Option<BlockId>) -> IndexerResult<()> {
         let sync_state = match self.context.blobscan_client().get_sync_state().await {
             Ok(state) => state,
@@ -96,29 +186,110 @@ impl Indexer {
             "Starting indexer…",
         );
 
+        let nodes_sync_check_handle =
+            self._start_nodes_sync_check_task(self.cancellation_token.clone());
+
+        if !self._wait_for_nodes_sync().await {
+            return Ok(());
+        }
+
         let (tx, mut rx) = mpsc::channel(32);
         let tx1 = tx.clone();
 
-        self._start_historical_sync_task(tx1, current_lower_block_id);
-        self._start_realtime_sync_task(tx, current_upper_block_id);
+        let handles = vec![
+            nodes_sync_check_handle,
+            self._start_historical_sync_task(tx1, current_lower_block_id),
+            self._start_realtime_sync_task(tx, current_upper_block_id),
+            self._start_failed_slots_chunks_retry_task(),
+            self._start_beacon_nodes_health_check_task(),
+        ];
 
-        while let Some(message) = rx.recv().await {
-            if let Err(error) = message {
-                error!(target = "indexer", ?error, "Indexer error occurred");
+        loop {
+            tokio::select! {
+                Some(message) = rx.recv() => {
+                    if let Err(error) = message {
+                        error!(target = "indexer", ?error, "Indexer error occurred");
 
-                return Err(error.into());
+                        return Err(error.into());
+                    }
+                }
+                _ = self.cancellation_token.cancelled() => break,
             }
         }
 
+        info!(
+            target = "indexer",
+            "Shutting down indexer. Waiting for in-flight slots to be processed…"
+        );
+
+        match tokio::time::timeout(SHUTDOWN_TIMEOUT, join_all(handles)).await {
+            Ok(_) => info!(target = "indexer", "Indexer shut down gracefully"),
+            Err(_) => warn!(
+                target = "indexer",
+                "Timed out after {} seconds waiting for indexing tasks to finish",
+                SHUTDOWN_TIMEOUT.as_secs()
+            ),
+        }
+
         Ok(())
     }
 
+    /// Indexes the given slots range without following the chain head and returns once done.
+    pub async fn run_range(
+        &mut self,
+        initial_block_id: BlockId,
+        final_block_id: BlockId,
+    ) -> IndexerResult<RangeSyncSummary> {
+        let start = Instant::now();
+        let nodes_sync_check_token = self.cancellation_token.child_token();
+        let _nodes_sync_check_guard = nodes_sync_check_token.clone().drop_guard();
+
+        self._start_nodes_sync_check_task(nodes_sync_check_token);
+
+        // Re-indexing a range mustn't move the sync state of the regular indexing nor leave
+        // retries behind for it. Failed slots chunks and pruned blobs slots are reported in the
+        // summary instead
+        let mut synchronizer = self
+            ._create_synchronizer_builder()
+            .with_checkpoints_saving(false)
+            .with_failed_slots_chunks_saving(false)
+            .with_pruned_blobs_slots_collection(true)
+            .with_index_batching(HISTORICAL_INDEX_BATCH_CONFIG)
+            .build(self.context.clone());
+
+        let initial_slot = synchronizer.resolve_to_slot(&initial_block_id).await?;
+        let final_slot = synchronizer.resolve_to_slot(&final_block_id).await?;
+
+        info!(
+            target = "indexer",
+            initial_slot, final_slot, "Starting range indexing…",
+        );
+
+        self._wait_for_nodes_sync().await;
+
+        synchronizer
+            .run(&BlockId::Slot(initial_slot), &BlockId::Slot(final_slot))
+            .await?;
+
+        Ok(RangeSyncSummary {
+            initial_slot,
+            final_slot,
+            last_synced_slot: synchronizer.get_last_synced_slot(),
+            failed_slots_chunks: synchronizer.get_failed_slots_chunks().to_vec(),
+            pruned_blobs_slots: synchronizer.get_pruned_blobs_slots().to_vec(),
+            elapsed: start.elapsed(),
+        })
+    }
+
     fn _start_historical_sync_task(
         &self,
         tx: mpsc::Sender<IndexerTaskResult>,
         start_block_id: BlockId,
     ) -> JoinHandle<IndexerTaskResult> {
-        let mut synchronizer = self._create_synchronizer();
+        let mut synchronizer = self
+            ._create_synchronizer_builder()
+            .with_index_batching(HISTORICAL_INDEX_BATCH_CONFIG)
+            .build(self.context.clone());
         let lowest_indexed_slot = self.lowest_indexed_slot;
 
         tokio::spawn(async move {
@@ -146,51 +317,227 @@ impl Indexer {
         start_block_id: BlockId,
     ) -> JoinHandle<IndexerTaskResult> {
         let task_context = self.context.clone();
+        let cancellation_token = self.cancellation_token.clone();
         let mut synchronizer = self._create_synchronizer();
 
         tokio::spawn(async move {
             let result: Result<(), IndexerError> = async {
+                let beacon_client = task_context.beacon_client();
                 let blobscan_client = task_context.blobscan_client();
-                let mut event_source = task_context
-                    .beacon_client()
-                    .subscribe_to_events(vec![Topic::Head])?;
-                let mut is_initial_sync_to_head = true;
-
-                while let Some(event) = event_source.next().await {
-                    match event {
-                        Ok(Event::Open) => {
-                            debug!(target = "indexer", "Listening for head block events…")
-                        }
-                        Ok(Event::Message(event)) => {
-                            let head_block_data =
-                                serde_json::from_str::<HeadBlockEventData>(&event.data)?;
-
-                            let head_block_id = &BlockId::Slot(head_block_data.slot);
-                            let initial_block_id = if is_initial_sync_to_head {
-                                is_initial_sync_to_head = false;
-                                &start_block_id
-                            } else {
-                                head_block_id
-                            };
-
-                            synchronizer.run(initial_block_id, head_block_id).await?;
-
-                            blobscan_client
-                                .update_sync_state(BlockchainSyncState {
-                                    last_lower_synced_slot: None,
-                                    last_upper_synced_slot: Some(head_block_data.slot),
-                                })
-                                .await?;
-                        }
-                        Err(error) => {
-                            event_source.close();
+                let mut reconnection_backoff = ExponentialBackoffBuilder::default()
+                    .with_max_interval(EVENTS_MAX_RECONNECTION_INTERVAL)
+                    .with_max_elapsed_time(None)
+                    .build();
+                // Slots are synced from the one following the last synced slot up to each new
+                // head, so missed or out-of-order head events don't leave gaps behind
+                let start_slot = match start_block_id {
+                    BlockId::Head => None,
+                    block_id => Some(synchronizer.resolve_to_slot(&block_id).await?),
+                };
+                // Starting at genesis, no slot has been synced yet
+                let mut last_synced_slot = start_slot.and_then(|slot| slot.checked_sub(1));
+
+                if let Some(finalized_block_header) =
+                    beacon_client.get_block_header(&BlockId::Finalized).await?
+                {
+                    task_context
+                        .set_last_finalized_slot(finalized_block_header.header.message.slot);
+                }
+
+                loop {
+                    let mut event_source = beacon_client
+                        .subscribe_to_events(vec![
+                            Topic::Head,
+                            Topic::FinalizedCheckpoint,
+                            Topic::ChainReorg,
+                        ])?
+                        .take_until(Box::pin(cancellation_token.cancelled()));
+                    let mut is_initial_sync_to_head = true;
+
+                    while let Some(event) = event_source.next().await {
+                        match event {
+                            Ok(Event::Open) => {
+                                reconnection_backoff.reset();
+
+                                debug!(
+                                    target = "indexer",
+                                    "Listening for head block and finalized checkpoint events…"
+                                )
+                            }
+                            Ok(Event::Message(event)) => match event.event.as_str() {
+                                "head" => {
+                                    let head_block_data =
+                                        serde_json::from_str::<HeadBlockEventData>(&event.data)?;
+
+                                    let head_slot = head_block_data.slot;
+                                    let initial_slot = match last_synced_slot {
+                                        Some(last_slot) if head_slot <= last_slot => {
+                                            debug!(
+                                                target = "indexer",
+                                                head_slot,
+                                                last_synced_slot = last_slot,
+                                                "Skipping stale head event"
+                                            );
+
+                                            continue;
+                                        }
+                                        Some(last_slot) => {
+                                            if !is_initial_sync_to_head && head_slot > last_slot + 1
+                                            {
+                                                warn!(
+                                                    target = "indexer",
+                                                    head_slot,
+                                                    last_synced_slot = last_slot,
+                                                    "Gap of {} slots detected between head events. Syncing missing slots…",
+                                                    head_slot - last_slot - 1
+                                                );
+                                            }
+
+                                            last_slot + 1
+                                        }
+                                        None => start_slot.unwrap_or(head_slot),
+                                    };
+
+                                    is_initial_sync_to_head = false;
+
+                                    synchronizer
+                                        .run(&BlockId::Slot(initial_slot), &BlockId::Slot(head_slot))
+                                        .await?;
+
+                                    // The synchronizer has already saved up to where it got
+                                    if cancellation_token.is_cancelled() {
+                                        return Ok(());
+                                    }
+
+                                    last_synced_slot = Some(head_slot);
+
+                                    blobscan_client
+                                        .update_sync_state(BlockchainSyncState {
+                                            last_lower_synced_slot: None,
+                                            last_upper_synced_slot: Some(head_slot),
+                                            last_finalized_slot: None,
+                                        })
+                                        .await?;
+
+                                    if !beacon_client.is_subscribed_to_best_node() {
+                                        event_source.get_mut().close();
 
-                            return Err(error.into());
+                                        info!(
+                                            target = "indexer",
+                                            "Moving events subscription to a healthier beacon node…"
+                                        );
+
+                                        break;
+                                    }
+                                }
+                                "finalized_checkpoint" => {
+                                    let finalized_checkpoint_data =
+                                        serde_json::from_str::<FinalizedCheckpointEventData>(
+                                            &event.data,
+                                        )?;
+
+                                    // The checkpoint epoch boundary slot may be empty, so we resolve the
+                                    // slot of the finalized block itself.
+                                    let finalized_block_header = match beacon_client
+                                        .get_block_header(&BlockId::Finalized)
+                                        .await?
+                                    {
+                                        Some(block_header) => block_header,
+                                        None => {
+                                            warn!(
+                                                target = "indexer",
+                                                epoch = finalized_checkpoint_data.epoch,
+                                                block = ?finalized_checkpoint_data.block,
+                                                "Finalized block not found"
+                                            );
+
+                                            continue;
+                                        }
+                                    };
+                                    let finalized_slot = finalized_block_header.header.message.slot;
+
+                                    task_context.set_last_finalized_slot(finalized_slot);
+
+                                    blobscan_client
+                                        .update_sync_state(BlockchainSyncState {
+                                            last_lower_synced_slot: None,
+                                            last_upper_synced_slot: None,
+                                            last_finalized_slot: Some(finalized_slot),
+                                        })
+                                        .await?;
+
+                                    info!(
+                                        target = "indexer",
+                                        epoch = finalized_checkpoint_data.epoch,
+                                        finalized_slot,
+                                        "New finalized checkpoint"
+                                    );
+                                }
+                                "chain_reorg" => {
+                                    let reorg_data =
+                                        serde_json::from_str::<ChainReorgEventData>(&event.data)?;
+
+                                    info!(
+                                        target = "indexer",
+                                        slot = reorg_data.slot,
+                                        depth = reorg_data.depth,
+                                        old_head_block = ?reorg_data.old_head_block,
+                                        new_head_block = ?reorg_data.new_head_block,
+                                        old_head_state = ?reorg_data.old_head_state,
+                                        new_head_state = ?reorg_data.new_head_state,
+                                        "Chain reorg event received"
+                                    );
+
+                                    // The event is only a second signal next to the parent root
+                                    // check, which still catches the reorg on the next head event
+                                    if let Err(error) =
+                                        synchronizer.handle_chain_reorg(&reorg_data).await
+                                    {
+                                        warn!(
+                                            target = "indexer",
+                                            ?error,
+                                            "Failed to handle chain reorg event"
+                                        );
+                                    }
+                                }
+                                unexpected_event => {
+                                    warn!(
+                                        target = "indexer",
+                                        event = unexpected_event,
+                                        "Received unexpected event"
+                                    );
+                                }
+                            },
+                            Err(error) => {
+                                event_source.get_mut().close();
+
+                                warn!(target = "indexer", ?error, "Events subscription failed");
+
+                                break;
+                            }
                         }
                     }
-                }
 
-                Ok(())
+                    if cancellation_token.is_cancelled() {
+                        return Ok(());
+                    }
+
+                    let reconnection_interval = reconnection_backoff
+                        .next_backoff()
+                        .unwrap_or(EVENTS_MAX_RECONNECTION_INTERVAL);
+
+                    info!(
+                        target = "indexer",
+                        last_synced_slot,
+                        "Reconnecting to events in {} seconds…",
+                        reconnection_interval.as_secs()
+                    );
+
+                    tokio::select! {
+                        _ = tokio::time::sleep(reconnection_interval) => {}
+                        _ = cancellation_token.cancelled() => return Ok(()),
+                    }
+                }
             }
             .await;
 
@@ -208,15 +555,636 @@ impl Indexer {
         })
     }
 
+    fn _start_failed_slots_chunks_retry_task(&self) -> JoinHandle<IndexerTaskResult> {
+        let task_context = self.context.clone();
+        let cancellation_token = self.cancellation_token.clone();
+
+        tokio::spawn(async move {
+            let failed_slots_chunks_store = task_context.failed_slots_chunks_store();
+            let mut retry_backoff = ExponentialBackoffBuilder::default()
+                .with_initial_interval(FAILED_SLOTS_CHUNKS_RETRY_INTERVAL)
+                .with_max_interval(FAILED_SLOTS_CHUNKS_MAX_RETRY_INTERVAL)
+                .with_max_elapsed_time(None)
+                .build();
+
+            loop {
+                let failed_slots_chunks = match failed_slots_chunks_store.get_all().await {
+                    Ok(chunks) => chunks,
+                    Err(error) => {
+                        warn!(
+                            target = "indexer",
+                            ?error,
+                            "Failed to get failed slots chunks"
+                        );
+
+                        vec![]
+                    }
+                };
+                let mut has_retries_failed = false;
+
+                for chunk in failed_slots_chunks {
+                    let mut slots_processor =
+                        SlotsProcessor::new(task_context.clone(), None, cancellation_token.clone());
+
+                    if let Err(error) = slots_processor
+                        .process_slots(chunk.initial_slot, chunk.final_slot)
+                        .await
+                    {
+                        warn!(
+                            target = "indexer",
+                            ?chunk,
+                            %error,
+                            "Failed to retry failed slots chunk"
+                        );
+
+                        has_retries_failed = true;
+
+                        continue;
+                    }
+
+                    // Leave the chunk to be retried on the next run
+                    if slots_processor.get_last_processed_slot() != Some(chunk.final_slot) {
+                        return Ok(());
+                    }
+
+                    info!(
+                        target = "indexer",
+                        initial_slot = chunk.initial_slot,
+                        final_slot = chunk.final_slot,
+                        "Failed slots chunk indexed successfully"
+                    );
+
+                    if let Err(error) = failed_slots_chunks_store.remove(&chunk).await {
+                        warn!(
+                            target = "indexer",
+                            ?chunk,
+                            ?error,
+                            "Failed to remove retried failed slots chunk"
+                        );
+                    }
+                }
+
+                let retry_interval = if has_retries_failed {
+                    retry_backoff
+                        .next_backoff()
+                        .unwrap_or(FAILED_SLOTS_CHUNKS_MAX_RETRY_INTERVAL)
+                } else {
+                    retry_backoff.reset();
+
+                    FAILED_SLOTS_CHUNKS_RETRY_INTERVAL
+                };
+
+                tokio::select! {
+                    _ = tokio::time::sleep(retry_interval) => {}
+                    _ = cancellation_token.cancelled() => return Ok(()),
+                }
+            }
+        })
+    }
+
+    /// Waits for the nodes to be synced. Returns `false` if the indexer is cancelled meanwhile.
+    async fn _wait_for_nodes_sync(&self) -> bool {
+        if !self.context.are_nodes_synced() {
+            info!(
+                target = "indexer",
+                "Waiting for beacon and execution nodes to be synced before indexing…"
+            );
+        }
+
+        tokio::select! {
+            _ = self.context.wait_for_nodes_synced() => true,
+            _ = self.cancellation_token.cancelled() => false,
+        }
+    }
+
+    /// Periodically checks whether the nodes are synced, pausing slots processing while they
+    /// aren't.
+    fn _start_nodes_sync_check_task(
+        &self,
+        cancellation_token: CancellationToken,
+    ) -> JoinHandle<IndexerTaskResult> {
+        let task_context = self.context.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let is_beacon_node_synced =
+                    match task_context.beacon_client().get_sync_status().await {
+                        Ok(Some(sync_status)) => {
+                            if !sync_status.is_synced() {
+                                warn!(
+                                    target = "indexer",
+                                    head_slot = sync_status.head_slot,
+                                    sync_distance = sync_status.sync_distance,
+                                    is_syncing = sync_status.is_syncing,
+                                    is_optimistic = sync_status.is_optimistic,
+                                    el_offline = sync_status.el_offline,
+                                    "Beacon node is not synced"
+                                );
+                            }
+
+                            sync_status.is_synced()
+                        }
+                        Ok(None) => false,
+                        Err(error) => {
+                            warn!(
+                                target = "indexer",
+                                ?error,
+                                "Failed to fetch beacon node sync status"
+                            );
+
+                            false
+                        }
+                    };
+                let is_execution_node_synced = task_context.provider().is_synced().await;
+                let are_nodes_synced = is_beacon_node_synced && is_execution_node_synced;
+
+                if are_nodes_synced != task_context.are_nodes_synced() {
+                    if are_nodes_synced {
+                        info!(target = "indexer", "Nodes synced. Indexing resumed");
+                    } else {
+                        warn!(
+                            target = "indexer",
+                            "Nodes out of sync. Indexing paused until they catch up…"
+                        );
+                    }
+                }
+
+                task_context.set_nodes_synced(are_nodes_synced);
+
+                tokio::select! {
+                    _ = tokio::time::sleep(NODES_SYNC_CHECK_INTERVAL) => {}
+                    _ = cancellation_token.cancelled() => return Ok(()),
+                }
+            }
+        })
+    }
+
+    fn _start_beacon_nodes_health_check_task(&self) -> JoinHandle<IndexerTaskResult> {
+        let task_context = self.context.clone();
+        let cancellation_token = self.cancellation_token.clone();
+
+        tokio::spawn(async move {
+            loop {
+                task_context.beacon_client().check_nodes_health().await;
+
+                tokio::select! {
+                    _ = tokio::time::sleep(BEACON_NODES_HEALTH_CHECK_INTERVAL) => {}
+                    _ = cancellation_token.cancelled() => return Ok(()),
+                }
+            }
+        })
+    }
+
     fn _create_synchronizer(&self) -> Synchronizer {
+        self._create_synchronizer_builder()
+            .build(self.context.clone())
+    }
+
+    fn _create_synchronizer_builder(&self) -> SynchronizerBuilder {
         let mut synchronizer_builder = SynchronizerBuilder::new();
 
-        synchronizer_builder.with_num_threads(self.num_threads);
+        synchronizer_builder
+            .with_num_threads(self.num_threads)
+            .with_cancellation_token(self.cancellation_token.clone());
 
         if let Some(slots_checkpoint) = self.slots_checkpoint {
             synchronizer_builder.with_slots_checkpoint(slots_checkpoint);
         }
 
-        synchronizer_builder.build(self.context.clone())
+        synchronizer_builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    use clap::Parser;
+    use ethers::types::H256;
+    use serde_json::{json, Value};
+
+    use crate::{
+        clients::blobscan::types::FailedSlotsChunk,
+        utils::mock_server::{
+            beacon_node_handler, create_chain, start_beacon_node, start_blobscan_api,
+            start_execution_node, start_server, Request, Response,
+        },
+    };
+
+    use super::*;
+
+    type Requests = Arc<Mutex<Vec<Request>>>;
+
+    /// Path of the events subscription of the realtime sync, with every topic in a single
+    /// comma-separated `topics` parameter.
+    const EVENTS_PATH: &str = "/eth/v1/events?topics=head,finalized_checkpoint,chain_reorg";
+
+    fn create_indexer(context: Context) -> Indexer {
+        Indexer {
+            context,
+            cancellation_token: CancellationToken::new(),
+            lowest_indexed_slot: 0,
+            num_threads: 4,
+            slots_checkpoint: None,
+        }
+    }
+
+    async fn start_synced_execution_node() -> String {
+        start_execution_node(Some(json!(false))).await
+    }
+
+    /// Starts a beacon node of the chain from 0 to 99 streaming a batch of events on each events
+    /// subscription, and returns its base URL along with the paths requested to it. Its finalized
+    /// block is the one at slot 16.
+    async fn start_beacon_node_with_events(
+        events: Vec<Vec<(&'static str, Value)>>,
+    ) -> (String, Arc<Mutex<Vec<String>>>) {
+        let handler = beacon_node_handler(create_chain(&[]));
+        let subscriptions = AtomicUsize::new(0);
+        let paths = Arc::new(Mutex::new(vec![]));
+        let requested_paths = paths.clone();
+        let base_url = start_server(move |mut request| {
+            requested_paths.lock().unwrap().push(request.path.clone());
+
+            if request.path == "/eth/v1/beacon/headers/finalized" {
+                request.path = "/eth/v1/beacon/headers/16".to_string();
+            }
+
+            if request.path != EVENTS_PATH {
+                return handler(request);
+            }
+
+            let Some(events) = events.get(subscriptions.fetch_add(1, Ordering::SeqCst)) else {
+                return Response::not_found();
+            };
+            let body = events
+                .iter()
+                .map(|(event, data)| format!("event: {event}\ndata: {data}\n\n"))
+                .collect::<String>();
+
+            Response {
+                status: 200,
+                headers: vec![("Content-Type".to_string(), "text/event-stream".to_string())],
+                body: body.into_bytes(),
+            }
+        })
+        .await;
+
+        (base_url, paths)
+    }
+
+    fn head_events(slots: &[u32]) -> Vec<(&'static str, Value)> {
+        slots
+            .iter()
+            .map(|slot| ("head", json!({ "slot": slot.to_string() })))
+            .collect()
+    }
+
+    /// Upper synced slots saved in the sync state, without repetitions.
+    fn get_upper_synced_slots(requests: &Requests) -> Vec<u64> {
+        let mut slots = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| {
+                request.method == "PUT" && request.path == "/api/blockchain-sync-state"
+            })
+            .filter_map(|request| {
+                serde_json::from_slice::<Value>(&request.body).unwrap()["lastUpperSyncedSlot"]
+                    .as_u64()
+            })
+            .collect::<Vec<_>>();
+
+        slots.dedup();
+
+        slots
+    }
+
+    /// Runs the realtime sync from the given block until the given slot is saved as synced.
+    async fn run_realtime_sync(
+        beacon_node_url: &str,
+        start_block_id: BlockId,
+        until_slot: u64,
+    ) -> Requests {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let context = Context::for_nodes(beacon_node_url, "http://127.0.0.1:1", &blobscan_api_url);
+        let indexer = create_indexer(context);
+        let (tx, _rx) = mpsc::channel(32);
+        let handle = indexer._start_realtime_sync_task(tx, start_block_id);
+
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while !get_upper_synced_slots(&requests).contains(&until_slot) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        indexer.cancellation_token.cancel();
+        handle.await.unwrap().unwrap();
+
+        requests
+    }
+
+    fn requested_header_slots(paths: &Arc<Mutex<Vec<String>>>) -> Vec<u32> {
+        let mut slots = paths
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|path| path.strip_prefix("/eth/v1/beacon/headers/"))
+            .filter_map(|block_id| block_id.parse().ok())
+            .collect::<Vec<_>>();
+
+        slots.sort();
+        slots.dedup();
+
+        slots
+    }
+
+    #[tokio::test]
+    async fn test_realtime_sync_fills_gaps_between_head_events() {
+        let (beacon_node_url, paths) =
+            start_beacon_node_with_events(vec![head_events(&[12, 20, 18])]).await;
+
+        let requests = run_realtime_sync(&beacon_node_url, BlockId::Slot(10), 20).await;
+
+        // The stale head event is skipped
+        assert_eq!(get_upper_synced_slots(&requests), vec![12, 20]);
+        assert_eq!(
+            requested_header_slots(&paths),
+            (10..=20).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_realtime_sync_from_head() {
+        let (beacon_node_url, paths) =
+            start_beacon_node_with_events(vec![head_events(&[15, 17])]).await;
+
+        let requests = run_realtime_sync(&beacon_node_url, BlockId::Head, 17).await;
+
+        assert_eq!(get_upper_synced_slots(&requests), vec![15, 17]);
+        assert_eq!(requested_header_slots(&paths), vec![15, 16, 17]);
+    }
+
+    #[tokio::test]
+    async fn test_realtime_sync_from_genesis() {
+        let (beacon_node_url, paths) = start_beacon_node_with_events(vec![head_events(&[3])]).await;
+
+        let requests = run_realtime_sync(&beacon_node_url, BlockId::Genesis, 3).await;
+
+        assert_eq!(get_upper_synced_slots(&requests), vec![3]);
+        assert_eq!(requested_header_slots(&paths), vec![0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_run_range() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let context = Context::for_nodes(
+            &start_beacon_node(create_chain(&[])).await,
+            &start_synced_execution_node().await,
+            &blobscan_api_url,
+        );
+        let mut indexer = create_indexer(context);
+
+        let summary = indexer
+            .run_range(BlockId::Slot(10), BlockId::Head)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.initial_slot, 10);
+        assert_eq!(summary.final_slot, 99);
+        assert!(summary.is_successful());
+        // The sync state of the regular indexing is left untouched
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_range_with_failed_slot() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let handler = beacon_node_handler(create_chain(&[]));
+        let beacon_node_url = start_server(move |request| {
+            if request.path == "/eth/v1/beacon/headers/45" {
+                return Response::error(500);
+            }
+
+            handler(request)
+        })
+        .await;
+        let context = Context::for_nodes(
+            &beacon_node_url,
+            &start_synced_execution_node().await,
+            &blobscan_api_url,
+        );
+        let mut indexer = create_indexer(context);
+
+        let summary = indexer
+            .run_range(BlockId::Slot(0), BlockId::Slot(99))
+            .await
+            .unwrap();
+
+        assert!(!summary.is_successful());
+        assert_eq!(
+            summary.failed_slots_chunks,
+            vec![FailedSlotsChunk::from((45, 59))]
+        );
+        // Failed slots chunks are only reported, not left to be retried
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_realtime_sync_reconnects_to_events() {
+        let (beacon_node_url, paths) =
+            start_beacon_node_with_events(vec![head_events(&[12]), head_events(&[20])]).await;
+
+        let requests = run_realtime_sync(&beacon_node_url, BlockId::Slot(10), 20).await;
+        let subscriptions = paths
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|path| *path == EVENTS_PATH)
+            .count();
+
+        // The slots missed while disconnected are synced on the first head after reconnecting
+        assert_eq!(subscriptions, 2);
+        assert_eq!(get_upper_synced_slots(&requests), vec![12, 20]);
+        assert_eq!(
+            requested_header_slots(&paths),
+            (10..=20).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_shuts_down_gracefully() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let (beacon_node_url, _) = start_beacon_node_with_events(vec![]).await;
+        let context = Context::for_nodes(
+            &beacon_node_url,
+            &start_synced_execution_node().await,
+            &blobscan_api_url,
+        );
+        let mut indexer = create_indexer(context);
+        let cancellation_token = indexer.cancellation_token.clone();
+        let handle = tokio::spawn(async move { indexer.run(None).await });
+        let has_synced_history = || {
+            requests.lock().unwrap().iter().any(|request| {
+                request.method == "PUT"
+                    && request.path == "/api/blockchain-sync-state"
+                    && serde_json::from_slice::<Value>(&request.body).unwrap()
+                        ["lastLowerSyncedSlot"]
+                        == json!(0)
+            })
+        };
+
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while !has_synced_history() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        cancellation_token.cancel();
+
+        // Every task finishes well before the shutdown timeout
+        tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_retry_failed_slots_chunks() {
+        let handler = beacon_node_handler(create_chain(&[]));
+        let beacon_node_url = start_server(move |request| {
+            if request.path == "/eth/v1/beacon/headers/45" {
+                return Response::error(500);
+            }
+
+            handler(request)
+        })
+        .await;
+        // Without a Blobscan API, the chunks are kept in the local file
+        let context =
+            Context::for_nodes(&beacon_node_url, "http://127.0.0.1:1", "http://127.0.0.1:1");
+        let failed_slots_chunks_store = context.failed_slots_chunks_store().clone();
+
+        failed_slots_chunks_store
+            .add(vec![(10, 20).into(), (40, 50).into()])
+            .await
+            .unwrap();
+
+        let indexer = create_indexer(context);
+        let handle = indexer._start_failed_slots_chunks_retry_task();
+
+        // The chunk that fails again is left to be retried later
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while failed_slots_chunks_store.get_all().await.unwrap()
+                != vec![FailedSlotsChunk::from((40, 50))]
+            {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        indexer.cancellation_token.cancel();
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_realtime_sync_tracks_finalized_checkpoints() {
+        let mut events = vec![(
+            "finalized_checkpoint",
+            json!({ "block": H256::from_low_u64_be(17), "state": H256::zero(), "epoch": "1" }),
+        )];
+
+        events.extend(head_events(&[20]));
+
+        let (beacon_node_url, paths) = start_beacon_node_with_events(vec![events]).await;
+
+        let requests = run_realtime_sync(&beacon_node_url, BlockId::Slot(10), 20).await;
+        let finalized_slots = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.method == "PUT")
+            .filter_map(|request| {
+                serde_json::from_slice::<Value>(&request.body).unwrap()["lastFinalizedSlot"]
+                    .as_u64()
+            })
+            .collect::<Vec<_>>();
+
+        // The finalized slot is the one of the finalized block, not the epoch boundary slot
+        assert_eq!(finalized_slots, vec![16]);
+        // A single subscription streams every topic
+        assert!(paths.lock().unwrap().iter().any(|path| path == EVENTS_PATH));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_start_slot() {
+        let execution_node_url = start_execution_node(Some(
+            serde_json::to_value(ethers::types::Block::<H256> {
+                number: Some(19426587.into()),
+                timestamp: 1710338135.into(),
+                ..Default::default()
+            })
+            .unwrap(),
+        ))
+        .await;
+        let indexer = create_indexer(Context::for_nodes(
+            "http://127.0.0.1:1",
+            &execution_node_url,
+            "http://127.0.0.1:1",
+        ));
+
+        // The Deneb fork on mainnet
+        for args in [
+            vec!["--from-block", "19426587"],
+            vec!["--from-timestamp", "1710338135"],
+            vec!["--from-date", "2024-03-13T13:55:35Z"],
+        ] {
+            let args = Args::try_parse_from(["blob-indexer"].into_iter().chain(args)).unwrap();
+
+            assert_eq!(
+                indexer.resolve_start_slot(&args).await.unwrap(),
+                Some(8626176)
+            );
+        }
+
+        let args = Args::try_parse_from(["blob-indexer"]).unwrap();
+
+        assert_eq!(indexer.resolve_start_slot(&args).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_realtime_sync_survives_failed_reorg_handling() {
+        let mut events = head_events(&[12]);
+
+        // The new head isn't known by the node
+        events.push((
+            "chain_reorg",
+            json!({
+                "slot": "13",
+                "depth": "1",
+                "old_head_block": H256::from_low_u64_be(13),
+                "new_head_block": H256::from_low_u64_be(999),
+                "old_head_state": H256::zero(),
+                "new_head_state": H256::zero()
+            }),
+        ));
+        events.extend(head_events(&[20]));
+
+        let (beacon_node_url, paths) = start_beacon_node_with_events(vec![events]).await;
+
+        let requests = run_realtime_sync(&beacon_node_url, BlockId::Slot(10), 20).await;
+        let new_head_path = format!("/eth/v1/beacon/headers/{:#x}", H256::from_low_u64_be(999));
+
+        assert!(paths.lock().unwrap().contains(&new_head_path));
+        assert_eq!(get_upper_synced_slots(&requests), vec![12, 20]);
     }
 }
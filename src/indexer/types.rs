@@ -1,5 +1,72 @@
+use std::time::Duration;
+
+use crate::clients::blobscan::types::FailedSlotsChunk;
+
 use super::error::{IndexerError, IndexingTaskError};
 
 pub type IndexerResult<T> = Result<T, IndexerError>;
 
 pub type IndexerTaskResult = Result<(), IndexingTaskError>;
+
+#[derive(Debug)]
+pub struct RangeSyncSummary {
+    pub initial_slot: u32,
+    pub final_slot: u32,
+    pub last_synced_slot: Option<u32>,
+    pub failed_slots_chunks: Vec<FailedSlotsChunk>,
+    /// Slots whose blocks weren't indexed as their blobs have been pruned.
+    pub pruned_blobs_slots: Vec<u32>,
+    pub elapsed: Duration,
+}
+
+impl RangeSyncSummary {
+    /// Whether every slot of the range has been synced without failures.
+    pub fn is_successful(&self) -> bool {
+        self.last_synced_slot == Some(self.final_slot)
+            && self.failed_slots_chunks.is_empty()
+            && self.pruned_blobs_slots.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_summary(last_synced_slot: Option<u32>) -> RangeSyncSummary {
+        RangeSyncSummary {
+            initial_slot: 100,
+            final_slot: 200,
+            last_synced_slot,
+            failed_slots_chunks: vec![],
+            pruned_blobs_slots: vec![],
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_summary_is_successful() {
+        assert!(create_summary(Some(200)).is_successful());
+        assert!(!create_summary(Some(150)).is_successful());
+        assert!(!create_summary(None).is_successful());
+    }
+
+    #[test]
+    fn test_summary_with_failed_slots_chunks_is_not_successful() {
+        let mut summary = create_summary(Some(200));
+
+        summary
+            .failed_slots_chunks
+            .push(FailedSlotsChunk::from((120, 139)));
+
+        assert!(!summary.is_successful());
+    }
+
+    #[test]
+    fn test_summary_with_pruned_blobs_slots_is_not_successful() {
+        let mut summary = create_summary(Some(200));
+
+        summary.pruned_blobs_slots.push(120);
+
+        assert!(!summary.is_successful());
+    }
+}
@@ -1,14 +1,20 @@
 use anyhow::{anyhow, Result as AnyhowResult};
 use args::Args;
 use clap::Parser;
+use clients::beacon::types::BlockId;
 use env::Environment;
 use indexer::Indexer;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
 use utils::telemetry::{get_subscriber, init_subscriber};
 
 mod args;
+mod chain_spec;
 mod clients;
 mod context;
 mod env;
+mod failed_slots_chunks;
 mod indexer;
 mod slots_processor;
 mod synchronizer;
@@ -23,6 +29,16 @@ pub fn print_banner(args: &Args, env: &Environment) {
     println!("Blobscan indexer (EIP-4844 blob indexer) - blobscan.com");
     println!("=======================================================");
 
+    if let Some(to_slot) = &args.to_slot {
+        println!(
+            "Slots range: {} - {}",
+            args.from_slot
+                .as_ref()
+                .map_or("none".to_string(), |slot| slot.to_string()),
+            to_slot
+        );
+    }
+
     if let Some(num_threads) = args.num_threads {
         println!("Number of threads: {}", num_threads);
     } else {
@@ -46,6 +62,17 @@ pub fn print_banner(args: &Args, env: &Environment) {
     println!("\n");
 }
 
+async fn wait_for_shutdown_signal() -> AnyhowResult<()> {
+    let mut sigterm = signal(SignalKind::terminate())?;
+
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result?,
+        _ = sigterm.recv() => {},
+    };
+
+    Ok(())
+}
+
 async fn run() -> AnyhowResult<()> {
     dotenv::dotenv().ok();
     let env = match Environment::from_env() {
@@ -72,10 +99,81 @@ async fn run() -> AnyhowResult<()> {
 
     print_banner(&args, &env);
 
-    Indexer::try_new(&env, &args)?
-        .run(args.from_slot)
-        .await
-        .map_err(|err| anyhow!(err))
+    let cancellation_token = CancellationToken::new();
+    let signal_cancellation_token = cancellation_token.clone();
+
+    tokio::spawn(async move {
+        if let Err(error) = wait_for_shutdown_signal().await {
+            error!(?error, "Failed to listen for shutdown signals");
+
+            return;
+        }
+
+        info!("Shutdown signal received. Stopping indexer…");
+
+        signal_cancellation_token.cancel();
+    });
+
+    let mut indexer = Indexer::try_new(&env, &args, cancellation_token).await?;
+
+    match (args.from_slot.clone(), args.to_slot.clone()) {
+        (Some(from_slot), Some(to_slot)) => {
+            let summary = indexer
+                .run_range(from_slot, to_slot)
+                .await
+                .map_err(|err| anyhow!(err))?;
+
+            println!("Range indexing summary");
+            println!("======================");
+            println!(
+                "Slots range: {} - {}",
+                summary.initial_slot, summary.final_slot
+            );
+            println!(
+                "Last synced slot: {}",
+                summary
+                    .last_synced_slot
+                    .map_or("none".to_string(), |slot| slot.to_string())
+            );
+            println!("Failed slots chunks: {}", summary.failed_slots_chunks.len());
+
+            for chunk in summary.failed_slots_chunks.iter() {
+                println!("  - {} - {}", chunk.initial_slot, chunk.final_slot);
+            }
+
+            println!(
+                "Slots with pruned blobs: {}",
+                summary.pruned_blobs_slots.len()
+            );
+
+            for slot in summary.pruned_blobs_slots.iter() {
+                println!("  - {slot}");
+            }
+
+            println!("Elapsed time: {:.2?}", summary.elapsed);
+
+            if summary.is_successful() {
+                Ok(())
+            } else {
+                Err(anyhow!("Slots range was not fully indexed"))
+            }
+        }
+        (from_slot, _) => {
+            let start_block_id = match from_slot {
+                Some(block_id) => Some(block_id),
+                None => indexer
+                    .resolve_start_slot(&args)
+                    .await
+                    .map_err(|err| anyhow!(err))?
+                    .map(BlockId::Slot),
+            };
+
+            indexer
+                .run(start_block_id)
+                .await
+                .map_err(|err| anyhow!(err))
+        }
+    }
 }
 
 #[tokio::main]
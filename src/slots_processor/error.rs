@@ -4,6 +4,17 @@ pub enum SlotProcessingError {
     ClientError(#[from] crate::clients::common::ClientError),
     #[error(transparent)]
     Provider(#[from] ethers::providers::ProviderError),
+    #[error("Blobs of slot {slot} have been pruned by the beacon node, which only keeps them from slot {retention_start_slot}")]
+    BlobsPruned {
+        slot: u32,
+        retention_start_slot: u32,
+    },
+    #[error("Blobs of slot {slot} are unavailable: {found_blobs} out of {expected_blobs} blob sidecars found")]
+    BlobsUnavailable {
+        slot: u32,
+        expected_blobs: usize,
+        found_blobs: usize,
+    },
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
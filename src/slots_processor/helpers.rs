@@ -2,11 +2,20 @@ use std::collections::HashMap;
 
 use ethers::types::{Block as EthersBlock, Transaction as EthersTransaction, H256};
 
+use anyhow::{bail, Context};
+
 use crate::{
-    clients::beacon::types::Blob as BeaconBlob,
-    utils::web3::{calculate_versioned_hash, get_tx_versioned_hashes},
+    clients::beacon::types::{Blob as BeaconBlob, KZG_COMMITMENT_INCLUSION_PROOF_DEPTH},
+    utils::{
+        ssz::{bytes_root, is_valid_merkle_branch, merkleize, uint64_chunk},
+        web3::{calculate_versioned_hash, get_tx_versioned_hashes},
+    },
 };
 
+/// Index of the first `blob_kzg_commitments` item within the subtree of depth
+/// `KZG_COMMITMENT_INCLUSION_PROOF_DEPTH` rooted at the block body.
+const BLOB_KZG_COMMITMENTS_SUBTREE_INDEX: u64 = 90112;
+
 pub fn create_tx_hash_versioned_hashes_mapping(
     block: &EthersBlock<EthersTransaction>,
 ) -> Result<HashMap<H256, Vec<H256>>, anyhow::Error> {
@@ -36,3 +45,166 @@ pub fn create_versioned_hash_blob_mapping(
 
     Ok(version_hash_to_blob)
 }
+
+/// Checks the blob sidecar was built for the given block and that its commitment is included
+/// in that block's body.
+pub fn verify_blob_inclusion_proof(
+    blob: &BeaconBlob,
+    block_root: H256,
+) -> Result<(), anyhow::Error> {
+    let header = &blob.signed_block_header.message;
+    let header_root = merkleize(&[
+        uint64_chunk(header.slot as u64),
+        uint64_chunk(header.proposer_index as u64),
+        header.parent_root,
+        header.state_root,
+        header.body_root,
+    ]);
+
+    if header_root != block_root {
+        bail!(
+            "Blob sidecar {} belongs to block {header_root:#x} instead of {block_root:#x}",
+            blob.index
+        );
+    }
+
+    let index = blob
+        .index
+        .parse::<u64>()
+        .with_context(|| format!("Invalid blob sidecar index {}", blob.index))?;
+    let commitment = hex::decode(
+        blob.kzg_commitment
+            .strip_prefix("0x")
+            .unwrap_or(&blob.kzg_commitment),
+    )
+    .with_context(|| format!("Invalid KZG commitment {}", blob.kzg_commitment))?;
+
+    if !is_valid_merkle_branch(
+        bytes_root(&commitment),
+        &blob.kzg_commitment_inclusion_proof,
+        KZG_COMMITMENT_INCLUSION_PROOF_DEPTH,
+        BLOB_KZG_COMMITMENTS_SUBTREE_INDEX + index,
+        header.body_root,
+    ) {
+        bail!(
+            "Blob sidecar {} commitment is not included in the body of block {block_root:#x}",
+            blob.index
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::beacon::types::BlobsResponse;
+
+    use super::*;
+
+    /// Root of the block of the sidecar fixture, computed from its signed block header.
+    const BLOCK_ROOT: &str = "0xc7d9a15af18c4e4db0f69d1e3d6b6f2b8b1ffea2291f65bb4134bbc4dece8782";
+
+    fn load_blob() -> BeaconBlob {
+        let response: BlobsResponse = serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/blob_sidecar.json"
+        )))
+        .unwrap();
+
+        response.data.into_iter().next().unwrap()
+    }
+
+    fn block_root() -> H256 {
+        BLOCK_ROOT.parse().unwrap()
+    }
+
+    #[test]
+    fn test_commitments_subtree_index_and_depth() {
+        // The body has up to 16 fields and `blob_kzg_commitments` is its 12th, a list whose
+        // length is mixed in above a tree of `MAX_BLOB_COMMITMENTS_PER_BLOCK` (4096) leaves
+        let body_depth = 4;
+        let commitments_field_index = 11;
+        let commitments_depth = 1 + 12;
+
+        assert_eq!(
+            KZG_COMMITMENT_INCLUSION_PROOF_DEPTH,
+            body_depth + commitments_depth
+        );
+        assert_eq!(
+            BLOB_KZG_COMMITMENTS_SUBTREE_INDEX,
+            commitments_field_index << commitments_depth
+        );
+    }
+
+    #[test]
+    fn test_commitment_merkle_branch() {
+        let blob = load_blob();
+        let commitment = hex::decode(&blob.kzg_commitment[2..]).unwrap();
+        let body_root = blob.signed_block_header.message.body_root;
+
+        assert_eq!(blob.index, "1");
+        assert!(is_valid_merkle_branch(
+            bytes_root(&commitment),
+            &blob.kzg_commitment_inclusion_proof,
+            KZG_COMMITMENT_INCLUSION_PROOF_DEPTH,
+            BLOB_KZG_COMMITMENTS_SUBTREE_INDEX + 1,
+            body_root,
+        ));
+        assert!(!is_valid_merkle_branch(
+            bytes_root(&commitment),
+            &blob.kzg_commitment_inclusion_proof,
+            KZG_COMMITMENT_INCLUSION_PROOF_DEPTH,
+            BLOB_KZG_COMMITMENTS_SUBTREE_INDEX,
+            body_root,
+        ));
+    }
+
+    #[test]
+    fn test_verify_blob_inclusion_proof() {
+        assert!(verify_blob_inclusion_proof(&load_blob(), block_root()).is_ok());
+    }
+
+    #[test]
+    fn test_verify_blob_inclusion_proof_with_tampered_proof() {
+        let mut blob = load_blob();
+
+        blob.kzg_commitment_inclusion_proof[3].0[0] ^= 1;
+
+        assert!(verify_blob_inclusion_proof(&blob, block_root()).is_err());
+    }
+
+    #[test]
+    fn test_verify_blob_inclusion_proof_with_truncated_proof() {
+        let mut blob = load_blob();
+
+        blob.kzg_commitment_inclusion_proof.pop();
+
+        assert!(verify_blob_inclusion_proof(&blob, block_root()).is_err());
+    }
+
+    #[test]
+    fn test_verify_blob_inclusion_proof_with_other_commitment() {
+        let mut blob = load_blob();
+
+        blob.kzg_commitment = format!("0x{}", "00".repeat(48));
+
+        assert!(verify_blob_inclusion_proof(&blob, block_root()).is_err());
+    }
+
+    #[test]
+    fn test_verify_blob_inclusion_proof_with_other_block() {
+        let blob = load_blob();
+        let parent_root = blob.signed_block_header.message.parent_root;
+
+        assert!(verify_blob_inclusion_proof(&blob, parent_root).is_err());
+    }
+
+    #[test]
+    fn test_verify_blob_inclusion_proof_with_tampered_header() {
+        let mut blob = load_blob();
+
+        blob.signed_block_header.message.proposer_index += 1;
+
+        assert!(verify_blob_inclusion_proof(&blob, block_root()).is_err());
+    }
+}
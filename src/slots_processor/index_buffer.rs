@@ -0,0 +1,209 @@
+use std::time::{Duration, Instant};
+
+use crate::clients::blobscan::types::IndexRequest;
+
+/// Thresholds at which the buffered blocks are sent to Blobscan. The first one reached
+/// triggers the flush.
+#[derive(Debug, Clone, Copy)]
+pub struct IndexBatchConfig {
+    /// Maximum amount of blocks buffered.
+    pub max_blocks: usize,
+    /// Maximum amount of blob bytes buffered.
+    pub max_size: usize,
+    /// Maximum time a block can stay buffered.
+    pub max_delay: Duration,
+}
+
+impl Default for IndexBatchConfig {
+    /// Blocks are sent as soon as they are buffered.
+    fn default() -> Self {
+        Self {
+            max_blocks: 1,
+            max_size: 0,
+            max_delay: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct IndexBuffer {
+    config: IndexBatchConfig,
+    entries: Vec<IndexRequest>,
+    size: usize,
+    first_entry_at: Option<Instant>,
+}
+
+impl IndexBuffer {
+    pub fn new(config: IndexBatchConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, entry: IndexRequest) {
+        self.size += entry
+            .blobs
+            .iter()
+            .map(|blob| blob.data.len())
+            .sum::<usize>();
+        self.first_entry_at.get_or_insert_with(Instant::now);
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[IndexRequest] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Slot of the oldest buffered block, which is where indexing has to resume from if
+    /// the buffer can't be flushed.
+    pub fn first_slot(&self) -> Option<u32> {
+        self.entries.first().map(|entry| entry.block.slot)
+    }
+
+    pub fn should_flush(&self) -> bool {
+        !self.is_empty()
+            && (self.entries.len() >= self.config.max_blocks
+                || self.size >= self.config.max_size
+                || self.first_entry_at.is_some_and(|first_entry_at| {
+                    first_entry_at.elapsed() >= self.config.max_delay
+                }))
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.size = 0;
+        self.first_entry_at = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{H256, U256, U64};
+
+    use crate::clients::blobscan::types::{Blob, Block};
+
+    use super::*;
+
+    fn create_entry(slot: u32, blob_sizes: &[usize]) -> IndexRequest {
+        IndexRequest {
+            block: Block {
+                number: U64::from(slot),
+                hash: H256::from_low_u64_be(slot.into()),
+                timestamp: U256::zero(),
+                slot,
+                blob_gas_used: U256::zero(),
+                excess_blob_gas: U256::zero(),
+            },
+            transactions: vec![],
+            blobs: blob_sizes
+                .iter()
+                .enumerate()
+                .map(|(index, size)| Blob {
+                    versioned_hash: H256::zero(),
+                    commitment: String::new(),
+                    data: vec![0; *size].into(),
+                    tx_hash: H256::zero(),
+                    index: index as u32,
+                })
+                .collect(),
+        }
+    }
+
+    fn create_index_buffer(max_blocks: usize, max_size: usize) -> IndexBuffer {
+        IndexBuffer::new(IndexBatchConfig {
+            max_blocks,
+            max_size,
+            max_delay: Duration::from_secs(3600),
+        })
+    }
+
+    #[test]
+    fn test_default_config_flushes_every_block() {
+        let mut index_buffer = IndexBuffer::new(IndexBatchConfig::default());
+
+        assert!(!index_buffer.should_flush());
+
+        index_buffer.push(create_entry(1, &[]));
+
+        assert!(index_buffer.should_flush());
+    }
+
+    #[test]
+    fn test_should_flush_on_max_blocks() {
+        let mut index_buffer = create_index_buffer(3, usize::MAX);
+
+        index_buffer.push(create_entry(1, &[]));
+        index_buffer.push(create_entry(2, &[]));
+
+        assert!(!index_buffer.should_flush());
+
+        index_buffer.push(create_entry(3, &[]));
+
+        assert!(index_buffer.should_flush());
+    }
+
+    #[test]
+    fn test_should_flush_on_max_size() {
+        let mut index_buffer = create_index_buffer(usize::MAX, 1000);
+
+        index_buffer.push(create_entry(1, &[400, 400]));
+
+        assert!(!index_buffer.should_flush());
+
+        index_buffer.push(create_entry(2, &[200]));
+
+        assert!(index_buffer.should_flush());
+    }
+
+    #[test]
+    fn test_should_flush_on_max_delay() {
+        let mut index_buffer = IndexBuffer::new(IndexBatchConfig {
+            max_blocks: usize::MAX,
+            max_size: usize::MAX,
+            max_delay: Duration::from_millis(100),
+        });
+
+        index_buffer.push(create_entry(1, &[]));
+
+        assert!(!index_buffer.should_flush());
+
+        std::thread::sleep(Duration::from_millis(100));
+
+        assert!(index_buffer.should_flush());
+    }
+
+    #[test]
+    fn test_first_slot() {
+        let mut index_buffer = create_index_buffer(usize::MAX, usize::MAX);
+
+        assert_eq!(index_buffer.first_slot(), None);
+
+        index_buffer.push(create_entry(10, &[]));
+        index_buffer.push(create_entry(12, &[]));
+
+        assert_eq!(index_buffer.first_slot(), Some(10));
+        assert_eq!(index_buffer.entries().len(), 2);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut index_buffer = create_index_buffer(usize::MAX, 1000);
+
+        index_buffer.push(create_entry(1, &[1000]));
+        index_buffer.clear();
+
+        assert!(index_buffer.is_empty());
+        assert_eq!(index_buffer.first_slot(), None);
+        assert!(!index_buffer.should_flush());
+
+        // The size of the cleared blocks doesn't count towards the next batch
+        index_buffer.push(create_entry(2, &[100]));
+
+        assert!(!index_buffer.should_flush());
+    }
+}
@@ -1,30 +1,45 @@
 use anyhow::{anyhow, Context as AnyhowContext, Result};
 
 use ethers::prelude::*;
-use tracing::{debug, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
 
 use crate::{
     clients::{
-        beacon::types::{BlockHeader, BlockId},
-        blobscan::types::{Blob, Block, Transaction},
+        beacon::types::{Block as BeaconBlock, BlockBody, BlockHeader, BlockId},
+        blobscan::types::{Blob, Block, IndexRequest, Transaction},
     },
     context::Context,
+    utils::kzg::verify_blob_kzg_proof,
 };
 
 use self::error::{SlotProcessingError, SlotsProcessorError};
-use self::helpers::{create_tx_hash_versioned_hashes_mapping, create_versioned_hash_blob_mapping};
+use self::helpers::{
+    create_tx_hash_versioned_hashes_mapping, create_versioned_hash_blob_mapping,
+    verify_blob_inclusion_proof,
+};
+use self::index_buffer::{IndexBatchConfig, IndexBuffer};
+use self::reorg::{find_reorg, get_reorged_blocks, Reorg};
 
 pub mod error;
 mod helpers;
+pub mod index_buffer;
+pub mod reorg;
 
 pub struct SlotsProcessor {
     context: Context,
+    cancellation_token: CancellationToken,
     last_block: Option<BlockData>,
+    last_processed_slot: Option<u32>,
+    processed_blocks: Vec<BlockData>,
+    pruned_blobs_slots: Vec<u32>,
+    index_buffer: IndexBuffer,
 }
 
 #[derive(Debug, Clone)]
 pub struct BlockData {
     pub root: H256,
+    pub parent_root: H256,
     pub slot: u32,
 }
 
@@ -32,52 +47,112 @@ impl From<BlockHeader> for BlockData {
     fn from(block_header: BlockHeader) -> Self {
         Self {
             root: block_header.root,
+            parent_root: block_header.header.message.parent_root,
             slot: block_header.header.message.slot,
         }
     }
 }
 
 impl SlotsProcessor {
-    pub fn new(context: Context) -> SlotsProcessor {
+    pub fn new(
+        context: Context,
+        last_block: Option<BlockData>,
+        cancellation_token: CancellationToken,
+    ) -> SlotsProcessor {
         Self {
             context,
-            last_block: None,
+            cancellation_token,
+            last_block,
+            last_processed_slot: None,
+            processed_blocks: vec![],
+            pruned_blobs_slots: vec![],
+            index_buffer: IndexBuffer::default(),
         }
     }
 
+    /// Buffers indexed blocks to send them to Blobscan in batches instead of one at a time.
+    pub fn with_index_batching(&mut self, config: IndexBatchConfig) -> &mut Self {
+        self.index_buffer = IndexBuffer::new(config);
+
+        self
+    }
+
+    /// Processes the given slots range. Once cancelled, no more slots are dispatched and it
+    /// returns early, so `get_last_processed_slot` needs to be checked to know where it stopped.
+    ///
+    /// A slot only counts as processed once its block has been sent to Blobscan, so when
+    /// buffering fails, processing is reported to have failed at the first buffered slot.
     pub async fn process_slots(
         &mut self,
         initial_slot: u32,
         final_slot: u32,
     ) -> Result<(), SlotsProcessorError> {
         let is_reverse_processing = initial_slot > final_slot;
+        let slots: Box<dyn Iterator<Item = u32> + Send> = if is_reverse_processing {
+            Box::new((final_slot..=initial_slot).rev())
+        } else {
+            Box::new(initial_slot..=final_slot)
+        };
+        let mut last_completed_slot = None;
+
+        for current_slot in slots {
+            if !self.context.are_nodes_synced() {
+                debug!(
+                    target = "slots_processor",
+                    slot = current_slot,
+                    "Waiting for nodes to be synced…"
+                );
 
-        if is_reverse_processing {
-            for current_slot in (final_slot..=initial_slot).rev() {
-                let result = self.process_slot(current_slot, Some(false)).await;
-
-                if let Err(error) = result {
-                    return Err(SlotsProcessorError::FailedSlotsProcessing {
-                        initial_slot,
-                        final_slot,
-                        failed_slot: current_slot,
-                        error,
-                    });
+                tokio::select! {
+                    _ = self.context.wait_for_nodes_synced() => {}
+                    _ = self.cancellation_token.cancelled() => {}
                 }
             }
-        } else {
-            for current_slot in initial_slot..=final_slot {
-                let result = self.process_slot(current_slot, Some(true)).await;
-
-                if let Err(error) = result {
-                    return Err(SlotsProcessorError::FailedSlotsProcessing {
-                        initial_slot,
-                        final_slot,
-                        failed_slot: current_slot,
-                        error,
-                    });
+
+            if self.cancellation_token.is_cancelled() {
+                break;
+            }
+
+            let result = async {
+                self.process_slot(current_slot, Some(!is_reverse_processing))
+                    .await?;
+
+                // Flushes the buffer when blocks have been waiting for too long
+                if self.index_buffer.should_flush() {
+                    self._flush_index_buffer().await?;
                 }
+
+                Ok(())
             }
+            .await;
+
+            if let Err(error) = result {
+                return Err(SlotsProcessorError::FailedSlotsProcessing {
+                    initial_slot,
+                    final_slot,
+                    failed_slot: self.index_buffer.first_slot().unwrap_or(current_slot),
+                    error,
+                });
+            }
+
+            last_completed_slot = Some(current_slot);
+
+            if self.index_buffer.is_empty() {
+                self.last_processed_slot = last_completed_slot;
+            }
+        }
+
+        if let Err(error) = self._flush_index_buffer().await {
+            return Err(SlotsProcessorError::FailedSlotsProcessing {
+                initial_slot,
+                final_slot,
+                failed_slot: self.index_buffer.first_slot().unwrap_or(initial_slot),
+                error,
+            });
+        }
+
+        if last_completed_slot.is_some() {
+            self.last_processed_slot = last_completed_slot;
         }
 
         Ok(())
@@ -88,47 +163,220 @@ impl SlotsProcessor {
         slot: u32,
         enable_reorg_detection: Option<bool>,
     ) -> Result<(), SlotProcessingError> {
-        if let Some(enable_reorg_detection) = enable_reorg_detection {
-            if enable_reorg_detection {
-                self._detect_and_handle_reorg(slot).await?;
+        let (beacon_block, block_root) = match self._get_block_with_root(slot).await? {
+            Some(block_with_root) => block_with_root,
+            None => {
+                debug!(
+                    target = "slots_processor",
+                    slot, "Skipping as there is no beacon block"
+                );
+
+                return Ok(());
+            }
+        };
+        let block = BlockData {
+            root: block_root,
+            parent_root: beacon_block.message.parent_root,
+            slot: beacon_block.message.slot,
+        };
+
+        if enable_reorg_detection.unwrap_or(false) {
+            self._detect_and_handle_reorg(&block).await?;
+        }
+
+        match self._index_block(&block, beacon_block).await {
+            Ok(()) => {}
+            // Without a blob source to get them from, pruned blobs can't be recovered by
+            // retrying, so the block is left unindexed and the slot recorded apart
+            Err(SlotProcessingError::BlobsPruned {
+                slot,
+                retention_start_slot,
+            }) if !self.context.beacon_client().has_blob_sources() => {
+                warn!(
+                    target = "slots_processor",
+                    slot,
+                    retention_start_slot,
+                    "Skipping as blobs have been pruned and no blob source is configured"
+                );
+
+                self.pruned_blobs_slots.push(slot);
+            }
+            Err(error) => return Err(error),
+        }
+
+        self.last_block = Some(block.clone());
+        self.processed_blocks.push(block);
+
+        Ok(())
+    }
+
+    pub fn get_last_block(&self) -> Option<BlockData> {
+        self.last_block.clone()
+    }
+
+    pub fn get_last_processed_slot(&self) -> Option<u32> {
+        self.last_processed_slot
+    }
+
+    /// Blocks processed so far, including the canonical ones re-indexed when handling reorgs.
+    pub fn get_processed_blocks(&self) -> &[BlockData] {
+        &self.processed_blocks
+    }
+
+    /// Slots whose blocks were skipped because their blobs have been pruned.
+    pub fn get_pruned_blobs_slots(&self) -> &[u32] {
+        &self.pruned_blobs_slots
+    }
+
+    /// Reports the blocks orphaned by a reorg and re-indexes the canonical ones
+    /// that replaced them, up to the given new head.
+    pub async fn handle_reorg(
+        &mut self,
+        new_head: BlockData,
+    ) -> Result<Reorg, SlotProcessingError> {
+        let old_head = self
+            .last_block
+            .clone()
+            .with_context(|| "Can't handle a reorg without a previously processed block")?;
+        let new_head_root = new_head.root;
+
+        // Orphaned blocks may still be buffered, so they need to be indexed before reporting them
+        self._flush_index_buffer().await?;
+
+        let reorg = find_reorg(&self.context, old_head, new_head.clone()).await?;
+        let reorged_blocks = get_reorged_blocks(&self.context, &reorg).await?;
+
+        info!(
+            target = "slots_processor",
+            depth = reorg.depth(),
+            common_ancestor_slot = reorg.common_ancestor.slot,
+            orphaned_blocks = format!("{:?}", reorged_blocks),
+            new_head_slot = new_head.slot,
+            "Reorg found. Re-indexing canonical blocks…"
+        );
+
+        self.context
+            .blobscan_client()
+            .handle_reorged_blocks(new_head_root, reorged_blocks)
+            .await?;
+
+        for block in reorg.canonical_blocks.iter() {
+            let beacon_block = self
+                .context
+                .beacon_client()
+                .get_block(&BlockId::Root(block.root))
+                .await?
+                .with_context(|| format!("Canonical block {:#x} not found", block.root))?;
+
+            self._index_block(block, beacon_block).await?;
+        }
+
+        self.processed_blocks.retain(|block| {
+            !reorg
+                .orphaned_blocks
+                .iter()
+                .any(|orphaned_block| orphaned_block.root == block.root)
+        });
+        self.processed_blocks
+            .extend(reorg.canonical_blocks.iter().cloned());
+
+        self.last_block = Some(new_head);
+
+        Ok(reorg)
+    }
+
+    /// Gets the block of the slot from the Era files when they cover it, or from the beacon
+    /// node otherwise.
+    async fn _get_block_with_root(
+        &self,
+        slot: u32,
+    ) -> Result<Option<(BeaconBlock, H256)>, SlotProcessingError> {
+        if let Some(era_store) = self.context.era_store() {
+            if era_store.contains_slot(slot) {
+                return Ok(era_store.get_block(slot).await?);
             }
         }
 
         let beacon_client = self.context.beacon_client();
-        let blobscan_client = self.context.blobscan_client();
-        let provider = self.context.provider();
 
-        let beacon_block = match beacon_client.get_block(&BlockId::Slot(slot)).await? {
-            Some(block) => block,
-            None => {
+        // The header and the block of the slot are fetched at the same time. The header gives the
+        // block root, and as the head may change in between, the block is only paired with it
+        // when both have the same state root
+        let block_id = BlockId::Slot(slot);
+        let (block_header, beacon_block) = tokio::try_join!(
+            beacon_client.get_block_header(&block_id),
+            beacon_client.get_block(&block_id),
+        )?;
+        let Some(block_header) = block_header else {
+            return Ok(None);
+        };
+        let block_root = block_header.root;
+        let header = &block_header.header.message;
+
+        let beacon_block = match beacon_block {
+            Some(beacon_block)
+                if beacon_block.message.slot == header.slot
+                    && beacon_block.message.state_root == header.state_root =>
+            {
+                beacon_block
+            }
+            _ => {
                 debug!(
                     target = "slots_processor",
-                    slot = slot,
-                    "Skipping as there is no beacon block"
+                    slot,
+                    block_root = ?block_root,
+                    "Block doesn't match the slot's header. Fetching it by root…"
                 );
 
-                return Ok(());
+                beacon_client
+                    .get_block(&BlockId::Root(block_root))
+                    .await?
+                    .with_context(|| format!("Block {block_root:#x} of slot {slot} not found"))?
             }
         };
 
-        let execution_payload = match beacon_block.message.body.execution_payload {
-            Some(payload) => payload,
-            None => {
+        if beacon_block.message.slot != slot {
+            return Err(anyhow!(
+                "Block {block_root:#x} belongs to slot {} instead of slot {slot}",
+                beacon_block.message.slot
+            )
+            .into());
+        }
+
+        Ok(Some((beacon_block, block_root)))
+    }
+
+    async fn _index_block(
+        &mut self,
+        block: &BlockData,
+        beacon_block: BeaconBlock,
+    ) -> Result<(), SlotProcessingError> {
+        let beacon_client = self.context.beacon_client();
+        let provider = self.context.provider();
+        let slot = block.slot;
+
+        let fork = beacon_block.fork;
+
+        let (execution_payload, blob_kzg_commitments) = match beacon_block.message.body {
+            BlockBody::Deneb {
+                execution_payload,
+                blob_kzg_commitments,
+            } => (execution_payload, blob_kzg_commitments),
+            BlockBody::PreMerge | BlockBody::PreDeneb { .. } => {
                 debug!(
                     target = "slots_processor",
-                    slot, "Skipping as beacon block doesn't contain execution payload"
+                    slot,
+                    %fork,
+                    "Skipping as blocks prior to Deneb don't contain blobs"
                 );
 
                 return Ok(());
             }
         };
 
-        let has_kzg_blob_commitments = match beacon_block.message.body.blob_kzg_commitments {
-            Some(commitments) => !commitments.is_empty(),
-            None => false,
-        };
+        let expected_blobs = blob_kzg_commitments.len();
 
-        if !has_kzg_blob_commitments {
+        if expected_blobs == 0 {
             debug!(
                 target = "slots_processor",
                 slot, "Skipping as beacon block doesn't contain blob kzg commitments"
@@ -137,13 +385,38 @@ impl SlotsProcessor {
             return Ok(());
         }
 
+        if let Some(max_blobs) = self.context.chain_spec().max_blobs_per_block(fork, slot) {
+            if expected_blobs as u64 > max_blobs {
+                return Err(anyhow!(
+                    "{fork} block contains {expected_blobs} blob KZG commitments, but at most {max_blobs} are allowed"
+                )
+                .into());
+            }
+        }
+
         let execution_block_hash = execution_payload.block_hash;
 
-        // Fetch execution block and perform some checks
+        // Both the execution block and the blobs only depend on the beacon block, so they
+        // are fetched concurrently
+
+        let (execution_block, blobs) = tokio::try_join!(
+            async {
+                provider
+                    .get_block_with_txs(execution_block_hash)
+                    .await
+                    .map_err(SlotProcessingError::Provider)
+            },
+            async {
+                beacon_client
+                    .get_blobs(&BlockId::Root(block.root))
+                    .await
+                    .map_err(SlotProcessingError::ClientError)
+            }
+        )?;
+
+        // Perform some checks on the execution block
 
-        let execution_block = provider
-            .get_block_with_txs(execution_block_hash)
-            .await?
+        let execution_block = execution_block
             .with_context(|| format!("Execution block {execution_block_hash} not found"))?;
 
         let tx_hash_to_versioned_hashes =
@@ -153,34 +426,87 @@ impl SlotsProcessor {
             return Err(anyhow!("Blocks mismatch: Beacon block contains blob KZG commitments, but the corresponding execution block does not contain any blob transactions").into());
         }
 
-        // Fetch blobs and perform some checks
+        // Perform some checks on the blobs
 
-        let blobs = match beacon_client
-            .get_blobs(&BlockId::Slot(slot))
-            .await
-            .map_err(SlotProcessingError::ClientError)?
-        {
-            Some(blobs) => {
-                if blobs.is_empty() {
-                    debug!(
-                        target = "slots_processor",
-                        slot, "Skipping as blobs sidecar is empty"
-                    );
-
-                    return Ok(());
-                } else {
-                    blobs
+        // The block commits to blobs, so missing sidecars mean the node pruned them or never
+        // got them, and the block can't be indexed without them
+
+        let blobs = blobs.unwrap_or_default();
+
+        if blobs.len() != expected_blobs {
+            let retention_start_slot = self.context.chain_spec().blobs_retention_start_slot();
+
+            warn!(
+                target = "slots_processor",
+                slot,
+                expected_blobs,
+                found_blobs = blobs.len(),
+                retention_start_slot,
+                "Blob sidecars missing from beacon node"
+            );
+
+            if blobs.is_empty() && slot < retention_start_slot {
+                return Err(SlotProcessingError::BlobsPruned {
+                    slot,
+                    retention_start_slot,
+                });
+            }
+
+            return Err(SlotProcessingError::BlobsUnavailable {
+                slot,
+                expected_blobs,
+                found_blobs: blobs.len(),
+            });
+        }
+
+        for blob in blobs.iter() {
+            if let Err(error) = verify_blob_inclusion_proof(blob, block.root) {
+                warn!(
+                    target = "slots_processor",
+                    slot,
+                    blob_index = blob.index,
+                    %error,
+                    "Blob sidecar inclusion proof verification failed"
+                );
+
+                return Err(error.into());
+            }
+        }
+
+        if self.context.verify_blob_kzg_proofs() {
+            let mut invalid_blobs = vec![];
+
+            for blob in blobs.iter() {
+                match verify_blob_kzg_proof(blob) {
+                    Ok(true) => {}
+                    Ok(false) => invalid_blobs.push(blob.index.clone()),
+                    Err(error) => {
+                        warn!(
+                            target = "slots_processor",
+                            slot,
+                            blob_index = blob.index,
+                            ?error,
+                            "Failed to verify blob KZG proof"
+                        );
+
+                        invalid_blobs.push(blob.index.clone());
+                    }
                 }
             }
-            None => {
-                debug!(
+
+            if !invalid_blobs.is_empty() {
+                warn!(
                     target = "slots_processor",
-                    slot, "Skipping as there is no blobs sidecar"
+                    slot,
+                    ?invalid_blobs,
+                    "Blobs don't match their KZG commitments"
                 );
 
-                return Ok(());
+                return Err(
+                    anyhow!("Blobs {invalid_blobs:?} failed KZG proof verification").into(),
+                );
             }
-        };
+        }
 
         // Create entities to be indexed
 
@@ -204,63 +530,426 @@ impl SlotsProcessor {
             }
         }
 
-        let tx_hashes = transactions_entities
-            .iter()
-            .map(|tx| tx.hash.to_string())
-            .collect::<Vec<String>>();
-        let blob_versioned_hashes = blob_entities
-            .iter()
-            .map(|blob| blob.versioned_hash.to_string())
-            .collect::<Vec<String>>();
+        self.index_buffer.push(IndexRequest {
+            block: block_entity,
+            transactions: transactions_entities,
+            blobs: blob_entities,
+        });
 
-        blobscan_client
-            .index(block_entity, transactions_entities, blob_entities)
-            .await
-            .map_err(SlotProcessingError::ClientError)?;
+        if self.index_buffer.should_flush() {
+            self._flush_index_buffer().await?;
+        }
 
-        info!(
-            target = "slots_processor",
-            slot,
-            block = execution_block_hash.to_string(),
-            transactions = format!("{:?}", tx_hashes),
-            blobs = format!("{:?}", blob_versioned_hashes),
-            "Block indexed successfully"
-        );
+        Ok(())
+    }
+
+    /// Sends the buffered blocks to Blobscan. They are kept buffered if it fails.
+    async fn _flush_index_buffer(&mut self) -> Result<(), SlotProcessingError> {
+        let blobscan_client = self.context.blobscan_client();
+        let entries = self.index_buffer.entries();
+
+        match entries {
+            [] => return Ok(()),
+            [entry] => blobscan_client.index(entry).await?,
+            _ => blobscan_client.index_batch(entries).await?,
+        }
+
+        for entry in entries {
+            let tx_hashes = entry
+                .transactions
+                .iter()
+                .map(|tx| tx.hash.to_string())
+                .collect::<Vec<String>>();
+            let blob_versioned_hashes = entry
+                .blobs
+                .iter()
+                .map(|blob| blob.versioned_hash.to_string())
+                .collect::<Vec<String>>();
+
+            info!(
+                target = "slots_processor",
+                slot = entry.block.slot,
+                block = entry.block.hash.to_string(),
+                transactions = format!("{:?}", tx_hashes),
+                blobs = format!("{:?}", blob_versioned_hashes),
+                "Block indexed successfully"
+            );
+        }
+
+        self.index_buffer.clear();
 
         Ok(())
     }
 
-    pub fn get_last_block(&self) -> Option<BlockData> {
-        self.last_block.clone()
+    async fn _detect_and_handle_reorg(
+        &mut self,
+        block: &BlockData,
+    ) -> Result<(), SlotProcessingError> {
+        let slot = block.slot;
+
+        if self.context.is_slot_finalized(slot) {
+            debug!(
+                target = "slots_processor",
+                slot, "Skipping reorg check as slot is finalized"
+            );
+        } else if let Some(last_block) = &self.last_block {
+            let parent_root = block.parent_root;
+
+            // The block may have already been processed when handling a reorg
+            if block.root != last_block.root && parent_root != last_block.root {
+                info!(target = "slots_processor", slot, "Block reorg detected");
+
+                // The new head of the canonical branch is the parent of the current block
+                let new_head: BlockData = self
+                    .context
+                    .beacon_client()
+                    .get_block_header(&BlockId::Root(parent_root))
+                    .await?
+                    .with_context(|| format!("Parent block {parent_root:#x} not found"))?
+                    .into();
+
+                self.handle_reorg(new_head).await?;
+            }
+        }
+
+        Ok(())
     }
+}
 
-    async fn _detect_and_handle_reorg(&mut self, slot: u32) -> Result<(), SlotProcessingError> {
-        let beacon_client = self.context.beacon_client();
-        let blobscan_client = self.context.blobscan_client();
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::{json, Value};
+
+    use crate::utils::{
+        mock_server::{
+            beacon_node_handler, create_block, create_chain, start_blobscan_api,
+            start_execution_node, start_server, state_root, Response,
+        },
+        web3::calculate_versioned_hash,
+    };
+
+    use super::*;
+
+    // Holesky block at slot 1409759, the one of the sidecar fixtures
+    const SLOT: u32 = 1409759;
+    const ROOT: &str = "0xc7d9a15af18c4e4db0f69d1e3d6b6f2b8b1ffea2291f65bb4134bbc4dece8782";
+    const PARENT_ROOT: &str = "0x83c2e78d90e9d4031c0de0db5782143ac38e0e7f41ad98f8b97dff90a270e6df";
+    const EXECUTION_BLOCK_HASH: &str =
+        "0x2982946c9fb44951fd9f65f73446a49d4fd6e0140b3f3ea857ff50eac7be69d7";
+    // Commitment of the blob at index 0, which the sidecar fixture lacks
+    const MISSING_COMMITMENT: &str = "0xadfebfe0d08b0b6f4e95bff79e2e029406e40781cca70c13f43104b81a03bf682ecfca570370b2836cb04251ef6656ae";
+    const COMMITMENT: &str = "0x95775c4349d5b03e71bba6452d79b79a88842c924ac480042fc7c20e8e5a28068eac650dc8fc9789c6515ca84b7514ef";
+    const JSON_BLOB_SIDECARS: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blob_sidecar.json"
+    ));
+
+    fn tx_hash() -> H256 {
+        H256::from_low_u64_be(1)
+    }
 
-        let beacon_block_header = match beacon_client.get_block_header(&BlockId::Slot(slot)).await?
-        {
-            Some(block_header) => block_header,
-            None => {
-                debug!(
-                    target = "slots_processor",
-                    slot, "Skipping as there is no beacon block header"
-                );
+    /// Starts a beacon node serving the fixtures block with the given commitments, and the
+    /// sidecar fixture when the blobs are available.
+    async fn start_beacon_node(commitments: &'static [&'static str], with_blobs: bool) -> String {
+        let handler = beacon_node_handler(vec![BlockData {
+            root: ROOT.parse().unwrap(),
+            parent_root: PARENT_ROOT.parse().unwrap(),
+            slot: SLOT,
+        }]);
+
+        start_server(move |request| {
+            if request.path == format!("/eth/v2/beacon/blocks/{ROOT}")
+                || request.path == format!("/eth/v2/beacon/blocks/{SLOT}")
+            {
+                return Response::json(json!({
+                    "version": "deneb",
+                    "data": {
+                        "message": {
+                            "slot": SLOT.to_string(),
+                            "parent_root": PARENT_ROOT,
+                            "state_root": state_root(ROOT.parse().unwrap()),
+                            "body": {
+                                "execution_payload": { "block_hash": EXECUTION_BLOCK_HASH },
+                                "blob_kzg_commitments": commitments
+                            }
+                        }
+                    }
+                }));
+            }
 
-                return Ok(());
+            if request.path == format!("/eth/v1/beacon/blob_sidecars/{ROOT}") {
+                if !with_blobs {
+                    return Response::not_found();
+                }
+
+                return Response::json(serde_json::from_str::<Value>(JSON_BLOB_SIDECARS).unwrap());
             }
-        };
 
-        if let Some(block) = &self.last_block {
-            if beacon_block_header.header.message.parent_root != block.root {
-                info!(target = "slots_processor", slot, "Block reorg detected");
+            handler(request)
+        })
+        .await
+    }
+
+    /// Starts an execution node serving the execution block of the fixtures block, with a
+    /// single transaction carrying its blob.
+    async fn start_execution_node_with_block() -> String {
+        let mut tx = serde_json::to_value(ethers::types::Transaction {
+            hash: tx_hash(),
+            gas_price: Some(U256::one()),
+            ..Default::default()
+        })
+        .unwrap();
+        let mut block = serde_json::to_value(ethers::types::Block::<Value> {
+            hash: Some(EXECUTION_BLOCK_HASH.parse().unwrap()),
+            number: Some(U64::from(1187436)),
+            ..Default::default()
+        })
+        .unwrap();
+
+        tx["maxFeePerBlobGas"] = json!("0x1");
+        tx["blobVersionedHashes"] = json!([calculate_versioned_hash(COMMITMENT).unwrap()]);
+        block["blobGasUsed"] = json!("0x20000");
+        block["excessBlobGas"] = json!("0x0");
+        block["transactions"] = json!([tx]);
+
+        start_execution_node(Some(block)).await
+    }
+
+    async fn create_slots_processor(
+        beacon_node_url: &str,
+        blobscan_api_url: &str,
+    ) -> SlotsProcessor {
+        let context = Context::for_nodes(
+            beacon_node_url,
+            &start_execution_node_with_block().await,
+            blobscan_api_url,
+        );
+
+        SlotsProcessor::new(context, None, CancellationToken::new())
+    }
+
+    #[tokio::test]
+    async fn test_process_slot() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let mut slots_processor = create_slots_processor(
+            &start_beacon_node(&[COMMITMENT], true).await,
+            &blobscan_api_url,
+        )
+        .await;
+
+        slots_processor.process_slot(SLOT, None).await.unwrap();
+
+        assert_eq!(
+            slots_processor.get_last_block().unwrap().root,
+            ROOT.parse().unwrap()
+        );
 
-                blobscan_client.handle_reorged_slot(slot).await?;
+        let requests = requests.lock().unwrap();
+
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/api/indexer/block-txs-blobs");
+
+        let body = serde_json::from_slice::<Value>(&requests[0].body).unwrap();
+
+        assert_eq!(body["block"]["slot"], json!(SLOT));
+        assert_eq!(body["block"]["hash"], json!(EXECUTION_BLOCK_HASH));
+        assert_eq!(body["transactions"][0]["hash"], json!(tx_hash()));
+        assert_eq!(body["blobs"][0]["commitment"], json!(COMMITMENT));
+        assert_eq!(body["blobs"][0]["txHash"], json!(tx_hash()));
+    }
+
+    #[tokio::test]
+    async fn test_process_slot_without_block() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let mut slots_processor = create_slots_processor(
+            &start_beacon_node(&[COMMITMENT], true).await,
+            &blobscan_api_url,
+        )
+        .await;
+
+        slots_processor.process_slot(SLOT + 1, None).await.unwrap();
+
+        assert!(slots_processor.get_last_block().is_none());
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_process_slot_without_execution_block() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let context = Context::for_nodes(
+            &start_beacon_node(&[COMMITMENT], true).await,
+            &start_execution_node(Some(Value::Null)).await,
+            &blobscan_api_url,
+        );
+        let mut slots_processor = SlotsProcessor::new(context, None, CancellationToken::new());
+
+        assert!(slots_processor.process_slot(SLOT, None).await.is_err());
+        assert!(slots_processor.get_last_block().is_none());
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_skip_pruned_blobs() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let mut slots_processor = create_slots_processor(
+            &start_beacon_node(&[COMMITMENT], false).await,
+            &blobscan_api_url,
+        )
+        .await;
+
+        // The slot is well before the retention period on mainnet
+        slots_processor.process_slot(SLOT, None).await.unwrap();
+
+        assert_eq!(slots_processor.get_pruned_blobs_slots(), [SLOT]);
+        assert!(slots_processor.get_last_block().is_some());
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_process_slot_with_missing_blobs() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let mut slots_processor = create_slots_processor(
+            &start_beacon_node(&[MISSING_COMMITMENT, COMMITMENT], true).await,
+            &blobscan_api_url,
+        )
+        .await;
+
+        // Only some of the blobs being found means they haven't been pruned
+        assert!(matches!(
+            slots_processor.process_slot(SLOT, None).await,
+            Err(SlotProcessingError::BlobsUnavailable {
+                expected_blobs: 2,
+                found_blobs: 1,
+                ..
+            })
+        ));
+        assert!(slots_processor.get_pruned_blobs_slots().is_empty());
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    fn enable_index_batching(slots_processor: &mut SlotsProcessor) {
+        slots_processor.with_index_batching(IndexBatchConfig {
+            max_blocks: 20,
+            max_size: usize::MAX,
+            max_delay: std::time::Duration::from_secs(3600),
+        });
+    }
+
+    #[tokio::test]
+    async fn test_process_slots_with_index_batching() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let mut slots_processor = create_slots_processor(
+            &start_beacon_node(&[COMMITMENT], true).await,
+            &blobscan_api_url,
+        )
+        .await;
+
+        enable_index_batching(&mut slots_processor);
+        slots_processor
+            .process_slots(SLOT - 1, SLOT + 1)
+            .await
+            .unwrap();
+
+        // The buffered block is sent once the range is processed
+        assert_eq!(slots_processor.get_last_processed_slot(), Some(SLOT + 1));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_process_slots_with_failed_index_batch() {
+        let mut slots_processor = create_slots_processor(
+            &start_beacon_node(&[COMMITMENT], true).await,
+            "http://127.0.0.1:1",
+        )
+        .await;
+
+        enable_index_batching(&mut slots_processor);
+
+        // Slots are only processed once their blocks have been indexed, so processing resumes
+        // from the first buffered one
+        assert!(matches!(
+            slots_processor.process_slots(SLOT - 1, SLOT + 1).await,
+            Err(SlotsProcessorError::FailedSlotsProcessing {
+                failed_slot: SLOT,
+                ..
+            })
+        ));
+        assert_eq!(slots_processor.get_last_processed_slot(), Some(SLOT - 1));
+    }
+
+    /// Starts a beacon node serving a chain without skipped slots, but the given block when
+    /// the block of its slot is requested by slot. Returns its URL along with the paths of the
+    /// blocks requested by root.
+    async fn start_beacon_node_changing_block(
+        block_by_slot: BlockData,
+    ) -> (String, Arc<Mutex<Vec<String>>>) {
+        let handler = beacon_node_handler(create_chain(&[]));
+        let block_by_slot_handler = beacon_node_handler(vec![block_by_slot.clone()]);
+        let block_by_root_paths = Arc::new(Mutex::new(vec![]));
+        let requested_paths = block_by_root_paths.clone();
+
+        let base_url = start_server(move |request| {
+            if request.path == format!("/eth/v2/beacon/blocks/{}", block_by_slot.slot) {
+                return block_by_slot_handler(request);
             }
-        }
 
-        self.last_block = Some(beacon_block_header.into());
+            if request.path.starts_with("/eth/v2/beacon/blocks/0x") {
+                requested_paths.lock().unwrap().push(request.path.clone());
+            }
 
-        Ok(())
+            handler(request)
+        })
+        .await;
+
+        (base_url, block_by_root_paths)
+    }
+
+    #[tokio::test]
+    async fn test_get_block_with_root() {
+        let (beacon_node_url, block_by_root_paths) =
+            start_beacon_node_changing_block(create_block(5, 6, 5)).await;
+        let slots_processor = SlotsProcessor::new(
+            Context::for_beacon_node(&beacon_node_url),
+            None,
+            CancellationToken::new(),
+        );
+
+        let (block, root) = slots_processor
+            ._get_block_with_root(5)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(root, H256::from_low_u64_be(6));
+        assert_eq!(block.message.slot, 5);
+        assert!(block_by_root_paths.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_block_with_root_when_block_changes() {
+        // The block got by slot is another one than the header's, as if the head had changed
+        // between both requests
+        let (beacon_node_url, block_by_root_paths) =
+            start_beacon_node_changing_block(create_block(5, 105, 4)).await;
+        let slots_processor = SlotsProcessor::new(
+            Context::for_beacon_node(&beacon_node_url),
+            None,
+            CancellationToken::new(),
+        );
+
+        let (block, root) = slots_processor
+            ._get_block_with_root(5)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(root, H256::from_low_u64_be(6));
+        assert_eq!(block.message.parent_root, H256::from_low_u64_be(5));
+        assert_eq!(
+            *block_by_root_paths.lock().unwrap(),
+            vec![format!("/eth/v2/beacon/blocks/{root:#x}")]
+        );
     }
 }
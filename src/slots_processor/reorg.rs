@@ -0,0 +1,262 @@
+use anyhow::anyhow;
+use ethers::types::H256;
+
+use crate::{
+    clients::{beacon::types::BlockId, blobscan::types::ReorgedBlock},
+    context::Context,
+};
+
+use super::{error::SlotProcessingError, BlockData};
+
+/// Maximum amount of slots we walk back looking for the common ancestor of two branches.
+const MAX_REORG_DEPTH: u32 = 128;
+
+#[derive(Debug)]
+pub struct Reorg {
+    pub common_ancestor: BlockData,
+    /// Blocks from the old branch that are no longer canonical, from newest to oldest.
+    pub orphaned_blocks: Vec<BlockData>,
+    /// Blocks from the new branch that replaced the orphaned ones, from oldest to newest.
+    pub canonical_blocks: Vec<BlockData>,
+}
+
+impl Reorg {
+    pub fn depth(&self) -> u32 {
+        self.orphaned_blocks
+            .first()
+            .map_or(0, |block| block.slot - self.common_ancestor.slot)
+    }
+}
+
+/// Walks back the parent roots of both heads until they meet at their common ancestor.
+pub async fn find_reorg(
+    context: &Context,
+    old_head: BlockData,
+    new_head: BlockData,
+) -> Result<Reorg, SlotProcessingError> {
+    let lowest_allowed_slot = old_head
+        .slot
+        .max(new_head.slot)
+        .saturating_sub(MAX_REORG_DEPTH);
+    let mut orphaned_blocks = vec![];
+    let mut canonical_blocks = vec![];
+    let mut old_branch_block = old_head;
+    let mut new_branch_block = new_head;
+
+    while old_branch_block.root != new_branch_block.root {
+        if old_branch_block.slot >= new_branch_block.slot {
+            let parent_root = old_branch_block.parent_root;
+
+            orphaned_blocks.push(old_branch_block);
+            old_branch_block = fetch_parent_block(context, parent_root).await?;
+        } else {
+            let parent_root = new_branch_block.parent_root;
+
+            canonical_blocks.push(new_branch_block);
+            new_branch_block = fetch_parent_block(context, parent_root).await?;
+        }
+
+        let lowest_slot = old_branch_block.slot.min(new_branch_block.slot);
+
+        if lowest_slot < lowest_allowed_slot {
+            return Err(anyhow!(
+                "Common ancestor not found within the last {MAX_REORG_DEPTH} slots"
+            )
+            .into());
+        }
+
+        if context.is_slot_finalized(lowest_slot) && old_branch_block.root != new_branch_block.root
+        {
+            return Err(anyhow!(
+                "Reorg goes beyond the finalized slot: branches still differ at slot {lowest_slot}"
+            )
+            .into());
+        }
+    }
+
+    canonical_blocks.reverse();
+
+    Ok(Reorg {
+        common_ancestor: old_branch_block,
+        orphaned_blocks,
+        canonical_blocks,
+    })
+}
+
+/// Fetches the execution block hashes of the orphaned blocks so they can be reported.
+pub async fn get_reorged_blocks(
+    context: &Context,
+    reorg: &Reorg,
+) -> Result<Vec<ReorgedBlock>, SlotProcessingError> {
+    let beacon_client = context.beacon_client();
+    let mut reorged_blocks = vec![];
+
+    for block in reorg.orphaned_blocks.iter() {
+        let execution_block_hash = beacon_client
+            .get_block(&BlockId::Root(block.root))
+            .await?
+            .and_then(|beacon_block| {
+                beacon_block
+                    .message
+                    .body
+                    .execution_payload()
+                    .map(|execution_payload| execution_payload.block_hash)
+            });
+
+        reorged_blocks.push(ReorgedBlock {
+            slot: block.slot,
+            block_root: block.root,
+            execution_block_hash,
+        });
+    }
+
+    Ok(reorged_blocks)
+}
+
+async fn fetch_parent_block(
+    context: &Context,
+    parent_root: H256,
+) -> Result<BlockData, SlotProcessingError> {
+    match context
+        .beacon_client()
+        .get_block_header(&BlockId::Root(parent_root))
+        .await?
+    {
+        Some(block_header) => Ok(block_header.into()),
+        None => Err(anyhow!("Block {parent_root:#x} not found while walking back a reorg").into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::mock_server::{
+        create_block, create_forked_chain, execution_block_hash, start_beacon_node,
+    };
+
+    use super::*;
+
+    fn roots(blocks: &[BlockData]) -> Vec<u64> {
+        blocks
+            .iter()
+            .map(|block| block.root.to_low_u64_be())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_find_reorg() {
+        let context = Context::for_beacon_node(&start_beacon_node(create_forked_chain()).await);
+        let reorg = find_reorg(
+            &context,
+            create_block(12, 112, 111),
+            create_block(13, 213, 212),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(reorg.common_ancestor.root, H256::from_low_u64_be(10));
+        assert_eq!(roots(&reorg.orphaned_blocks), vec![112, 111]);
+        assert_eq!(roots(&reorg.canonical_blocks), vec![212, 213]);
+        assert_eq!(reorg.depth(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_find_reorg_of_old_head_sibling() {
+        let context = Context::for_beacon_node(&start_beacon_node(create_forked_chain()).await);
+        let reorg = find_reorg(
+            &context,
+            create_block(11, 111, 10),
+            create_block(12, 212, 10),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(reorg.common_ancestor.slot, 10);
+        assert_eq!(roots(&reorg.orphaned_blocks), vec![111]);
+        assert_eq!(roots(&reorg.canonical_blocks), vec![212]);
+        assert_eq!(reorg.depth(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_reorg_without_reorg() {
+        let context = Context::for_beacon_node(&start_beacon_node(create_forked_chain()).await);
+        let reorg = find_reorg(&context, create_block(10, 10, 9), create_block(12, 212, 10))
+            .await
+            .unwrap();
+
+        assert!(reorg.orphaned_blocks.is_empty());
+        assert_eq!(roots(&reorg.canonical_blocks), vec![212]);
+        assert_eq!(reorg.depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_find_reorg_beyond_finalized_slot() {
+        let context = Context::for_beacon_node(&start_beacon_node(create_forked_chain()).await);
+
+        context.set_last_finalized_slot(11);
+
+        assert!(find_reorg(
+            &context,
+            create_block(12, 112, 111),
+            create_block(13, 213, 212)
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_reorg_beyond_max_depth() {
+        let context = Context::for_beacon_node(
+            &start_beacon_node(vec![
+                create_block(100, 100, 99),
+                create_block(101, 101, 100),
+                create_block(100 + MAX_REORG_DEPTH + 1, 300, 100),
+            ])
+            .await,
+        );
+
+        assert!(find_reorg(
+            &context,
+            create_block(100 + MAX_REORG_DEPTH + 1, 300, 100),
+            create_block(100 + MAX_REORG_DEPTH + 1, 301, 101),
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_reorg_with_missing_parent() {
+        let context = Context::for_beacon_node(&start_beacon_node(create_forked_chain()).await);
+
+        assert!(find_reorg(
+            &context,
+            create_block(12, 112, 111),
+            create_block(13, 213, 999)
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_reorged_blocks() {
+        let context = Context::for_beacon_node(&start_beacon_node(create_forked_chain()).await);
+        let reorg = find_reorg(
+            &context,
+            create_block(12, 112, 111),
+            create_block(13, 213, 212),
+        )
+        .await
+        .unwrap();
+        let reorged_blocks = get_reorged_blocks(&context, &reorg).await.unwrap();
+
+        assert_eq!(reorged_blocks.len(), 2);
+
+        for (reorged_block, orphaned_block) in reorged_blocks.iter().zip(&reorg.orphaned_blocks) {
+            assert_eq!(reorged_block.slot, orphaned_block.slot);
+            assert_eq!(reorged_block.block_root, orphaned_block.root);
+            assert_eq!(
+                reorged_block.execution_block_hash,
+                Some(execution_block_hash(orphaned_block.root))
+            );
+        }
+    }
+}
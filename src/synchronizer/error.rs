@@ -1,4 +1,10 @@
-use crate::{clients::beacon::types::BlockId, slots_processor::error::SlotsProcessorError};
+use ethers::types::H256;
+
+use crate::{
+    clients::beacon::types::BlockId,
+    failed_slots_chunks::error::FailedSlotsChunksStoreError,
+    slots_processor::error::{SlotProcessingError, SlotsProcessorError},
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum SynchronizerError {
@@ -20,6 +26,16 @@ pub enum SynchronizerError {
         slot: u32,
         error: crate::clients::common::ClientError,
     },
+    #[error("Failed to save failed slots chunks: {error}\n{chunks_error}")]
+    FailedSlotsChunksSave {
+        error: FailedSlotsChunksStoreError,
+        chunks_error: Box<SynchronizerError>,
+    },
+    #[error("Failed to handle reorg to new head {new_head_block:#x}: {error}")]
+    FailedReorgHandling {
+        new_head_block: H256,
+        error: SlotProcessingError,
+    },
     #[error(transparent)]
     FailedSlotsProcessing(#[from] SlotsProcessorError),
     #[error(transparent)]
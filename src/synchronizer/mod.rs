@@ -1,12 +1,26 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
 use anyhow::anyhow;
 use futures::future::join_all;
-use tokio::task::JoinHandle;
-use tracing::{debug_span, info, Instrument};
+use tokio::task::{JoinError, JoinHandle};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, debug_span, info, warn, Instrument};
 
 use crate::{
-    clients::{beacon::types::BlockId, blobscan::types::BlockchainSyncState, common::ClientError},
+    clients::{
+        beacon::types::{BlockId, ChainReorgEventData},
+        blobscan::types::{BlockchainSyncState, FailedSlotsChunk},
+        common::ClientError,
+    },
     context::Context,
-    slots_processor::{error::SlotsProcessorError, BlockData, SlotsProcessor},
+    slots_processor::{
+        error::{SlotProcessingError, SlotsProcessorError},
+        index_buffer::IndexBatchConfig,
+        BlockData, SlotsProcessor,
+    },
 };
 
 use self::error::{SlotsChunksErrors, SynchronizerError};
@@ -16,24 +30,54 @@ pub mod error;
 #[derive(Debug)]
 pub struct SynchronizerBuilder {
     num_threads: u32,
-    min_slots_per_thread: u32,
+    slots_per_batch: u32,
     slots_checkpoint: u32,
+    save_checkpoints: bool,
+    save_failed_slots_chunks: bool,
+    collect_pruned_blobs_slots: bool,
+    index_batch_config: IndexBatchConfig,
+    cancellation_token: CancellationToken,
 }
 
 pub struct Synchronizer {
     context: Context,
     num_threads: u32,
-    min_slots_per_thread: u32,
+    slots_per_batch: u32,
     slots_checkpoint: u32,
+    save_checkpoints: bool,
+    save_failed_slots_chunks: bool,
+    collect_pruned_blobs_slots: bool,
+    index_batch_config: IndexBatchConfig,
+    cancellation_token: CancellationToken,
     last_synced_block: Option<BlockData>,
+    last_synced_slot: Option<u32>,
+    failed_slots_chunks: Vec<FailedSlotsChunk>,
+    pruned_blobs_slots: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SlotsBatch {
+    index: usize,
+    initial_slot: u32,
+    final_slot: u32,
+}
+
+struct SlotsBatchResult {
+    batch: SlotsBatch,
+    result: Result<Result<SlotsProcessor, SlotsProcessorError>, JoinError>,
 }
 
 impl Default for SynchronizerBuilder {
     fn default() -> Self {
         SynchronizerBuilder {
             num_threads: 1,
-            min_slots_per_thread: 50,
+            slots_per_batch: 20,
             slots_checkpoint: 1000,
+            save_checkpoints: true,
+            save_failed_slots_chunks: true,
+            collect_pruned_blobs_slots: false,
+            index_batch_config: IndexBatchConfig::default(),
+            cancellation_token: CancellationToken::new(),
         }
     }
 }
@@ -54,13 +98,61 @@ impl SynchronizerBuilder {
         self
     }
 
+    /// Whether the last synced slot is saved in the sync state at every checkpoint.
+    pub fn with_checkpoints_saving(&mut self, save_checkpoints: bool) -> &mut Self {
+        self.save_checkpoints = save_checkpoints;
+
+        self
+    }
+
+    /// Whether failed slots chunks are saved in the failed slots chunks store to be retried by
+    /// the indexer. They are kept in the synchronizer either way.
+    pub fn with_failed_slots_chunks_saving(&mut self, save_failed_slots_chunks: bool) -> &mut Self {
+        self.save_failed_slots_chunks = save_failed_slots_chunks;
+
+        self
+    }
+
+    /// Whether the slots left unindexed because their blobs have been pruned are kept to be
+    /// reported. They are only logged otherwise, as a long-lived synchronizer would keep
+    /// accumulating them.
+    pub fn with_pruned_blobs_slots_collection(
+        &mut self,
+        collect_pruned_blobs_slots: bool,
+    ) -> &mut Self {
+        self.collect_pruned_blobs_slots = collect_pruned_blobs_slots;
+
+        self
+    }
+
+    /// Sends the synced blocks to Blobscan in batches instead of one at a time.
+    pub fn with_index_batching(&mut self, index_batch_config: IndexBatchConfig) -> &mut Self {
+        self.index_batch_config = index_batch_config;
+
+        self
+    }
+
+    pub fn with_cancellation_token(&mut self, cancellation_token: CancellationToken) -> &mut Self {
+        self.cancellation_token = cancellation_token;
+
+        self
+    }
+
     pub fn build(&self, context: Context) -> Synchronizer {
         Synchronizer {
             context,
             num_threads: self.num_threads,
-            min_slots_per_thread: self.min_slots_per_thread,
+            slots_per_batch: self.slots_per_batch,
             slots_checkpoint: self.slots_checkpoint,
+            save_checkpoints: self.save_checkpoints,
+            save_failed_slots_chunks: self.save_failed_slots_chunks,
+            collect_pruned_blobs_slots: self.collect_pruned_blobs_slots,
+            index_batch_config: self.index_batch_config,
+            cancellation_token: self.cancellation_token.clone(),
             last_synced_block: None,
+            last_synced_slot: None,
+            failed_slots_chunks: vec![],
+            pruned_blobs_slots: vec![],
         }
     }
 }
@@ -71,14 +163,18 @@ impl Synchronizer {
         initial_block_id: &BlockId,
         final_block_id: &BlockId,
     ) -> Result<(), SynchronizerError> {
-        let initial_slot = self._resolve_to_slot(initial_block_id).await?;
-        let mut final_slot = self._resolve_to_slot(final_block_id).await?;
+        let initial_slot = self.resolve_to_slot(initial_block_id).await?;
+        let mut final_slot = self.resolve_to_slot(final_block_id).await?;
 
         loop {
             self._sync_slots_by_checkpoints(initial_slot, final_slot)
                 .await?;
 
-            let latest_final_slot = self._resolve_to_slot(final_block_id).await?;
+            if self.cancellation_token.is_cancelled() {
+                return Ok(());
+            }
+
+            let latest_final_slot = self.resolve_to_slot(final_block_id).await?;
 
             if final_slot == latest_final_slot {
                 return Ok(());
@@ -88,89 +184,320 @@ impl Synchronizer {
         }
     }
 
-    async fn _sync_slots(&mut self, from_slot: u32, to_slot: u32) -> Result<(), SynchronizerError> {
-        let is_reverse_sync = to_slot < from_slot;
-        let unprocessed_slots = to_slot.abs_diff(from_slot) + 1;
-        let slots_per_thread = std::cmp::max(
-            self.min_slots_per_thread,
-            unprocessed_slots / self.num_threads,
-        );
-        let num_threads = std::cmp::max(1, unprocessed_slots / slots_per_thread);
-        let remaining_slots = unprocessed_slots % num_threads;
+    /// Last slot up to which all slots have been synced.
+    pub fn get_last_synced_slot(&self) -> Option<u32> {
+        self.last_synced_slot
+    }
+
+    /// Slots chunks that failed to be synced and were saved to be retried later.
+    pub fn get_failed_slots_chunks(&self) -> &[FailedSlotsChunk] {
+        &self.failed_slots_chunks
+    }
+
+    /// Slots left unindexed because their blobs have been pruned and can't be retrieved, when
+    /// their collection is enabled.
+    pub fn get_pruned_blobs_slots(&self) -> &[u32] {
+        &self.pruned_blobs_slots
+    }
+
+    /// Handles a reorg notified by the beacon node, cross-checking it against the last synced block.
+    pub async fn handle_chain_reorg(
+        &mut self,
+        reorg_event: &ChainReorgEventData,
+    ) -> Result<(), SynchronizerError> {
+        let last_synced_block = match &self.last_synced_block {
+            Some(block) => block.clone(),
+            None => {
+                debug!(
+                    target = "synchronizer",
+                    "Skipping reorg handling as no block has been synced yet"
+                );
+
+                return Ok(());
+            }
+        };
+
+        if last_synced_block.root == reorg_event.new_head_block {
+            debug!(
+                target = "synchronizer",
+                new_head_block = ?reorg_event.new_head_block,
+                "Skipping reorg handling as new head has already been synced"
+            );
 
-        let mut handles: Vec<JoinHandle<Result<Option<BlockData>, SlotsProcessorError>>> = vec![];
+            return Ok(());
+        }
+
+        if last_synced_block.root != reorg_event.old_head_block {
+            warn!(
+                target = "synchronizer",
+                last_synced_block_root = ?last_synced_block.root,
+                old_head_block = ?reorg_event.old_head_block,
+                "Reorg old head doesn't match the last synced block"
+            );
+        }
 
-        for i in 0..num_threads {
-            let mut slots_processor = SlotsProcessor::new(self.context.clone());
-            let thread_total_slots = slots_per_thread
-                + if i == num_threads - 1 {
-                    remaining_slots
+        let result: Result<_, SlotProcessingError> = async {
+            let new_head = self
+                .context
+                .beacon_client()
+                .get_block_header(&BlockId::Root(reorg_event.new_head_block))
+                .await?
+                .ok_or_else(|| anyhow!("New head block not found"))?;
+            let mut slots_processor = SlotsProcessor::new(
+                self.context.clone(),
+                Some(last_synced_block),
+                self.cancellation_token.clone(),
+            );
+
+            let reorg = slots_processor.handle_reorg(new_head.into()).await?;
+
+            Ok((reorg, slots_processor.get_last_block()))
+        }
+        .await;
+
+        let (reorg, new_last_synced_block) = match result {
+            Ok(result) => result,
+            Err(error) => {
+                return Err(SynchronizerError::FailedReorgHandling {
+                    new_head_block: reorg_event.new_head_block,
+                    error,
+                })
+            }
+        };
+
+        if reorg.depth() != reorg_event.depth {
+            warn!(
+                target = "synchronizer",
+                node_depth = reorg_event.depth,
+                depth = reorg.depth(),
+                "Reorg depth reported by the beacon node doesn't match the computed one"
+            );
+        }
+
+        self.last_synced_block = new_last_synced_block;
+
+        Ok(())
+    }
+
+    /// Processes the given slots in parallel and returns the furthest slot up to which all
+    /// the slots have been handled, which may fall short of `to_slot` if the synchronizer is
+    /// cancelled. Slots that fail are saved to be retried later.
+    ///
+    /// Slots are split into small batches placed in a shared queue that a pool of workers pulls
+    /// from, so a slow batch doesn't leave the rest of the workers idle.
+    async fn _sync_slots(
+        &mut self,
+        from_slot: u32,
+        to_slot: u32,
+    ) -> Result<Option<u32>, SynchronizerError> {
+        let is_reverse_sync = to_slot < from_slot;
+        let unprocessed_slots = to_slot.abs_diff(from_slot) + 1;
+        let num_batches = unprocessed_slots.div_ceil(self.slots_per_batch);
+        let batches = (0..num_batches)
+            .map(|i| {
+                let batch_total_slots = std::cmp::min(
+                    self.slots_per_batch,
+                    unprocessed_slots - i * self.slots_per_batch,
+                );
+                let batch_initial_slot = if is_reverse_sync {
+                    from_slot - i * self.slots_per_batch
                 } else {
-                    0
+                    from_slot + i * self.slots_per_batch
                 };
-            let thread_initial_slot = if is_reverse_sync {
-                from_slot - i * slots_per_thread
-            } else {
-                from_slot + i * slots_per_thread
-            };
-            let thread_final_slot = if is_reverse_sync {
-                thread_initial_slot - thread_total_slots + 1
+                let batch_final_slot = if is_reverse_sync {
+                    batch_initial_slot + 1 - batch_total_slots
+                } else {
+                    batch_initial_slot + batch_total_slots - 1
+                };
+
+                SlotsBatch {
+                    index: i as usize,
+                    initial_slot: batch_initial_slot,
+                    final_slot: batch_final_slot,
+                }
+            })
+            .collect::<VecDeque<SlotsBatch>>();
+        let queue = Arc::new(Mutex::new(batches));
+        let num_workers = std::cmp::min(self.num_threads, num_batches);
+
+        let mut handles: Vec<JoinHandle<Vec<SlotsBatchResult>>> = vec![];
+
+        for _ in 0..num_workers {
+            let queue = queue.clone();
+            let context = self.context.clone();
+            let cancellation_token = self.cancellation_token.clone();
+            let index_batch_config = self.index_batch_config;
+            // Only the first batch continues from the last synced block, so reorgs happening
+            // between consecutive runs can be detected
+            let first_batch_last_block = if is_reverse_sync {
+                None
             } else {
-                thread_initial_slot + thread_total_slots - 1
+                self.last_synced_block.clone()
             };
 
-            let synchronizer_thread_span = tracing::trace_span!(
-                "synchronizer_thread",
-                chunk_initial_slot = thread_initial_slot,
-                chunk_final_slot = thread_final_slot
-            );
+            let handle = tokio::spawn(async move {
+                let mut batch_results = vec![];
+
+                loop {
+                    if cancellation_token.is_cancelled() {
+                        break;
+                    }
+
+                    let batch = match queue.lock().unwrap().pop_front() {
+                        Some(batch) => batch,
+                        None => break,
+                    };
+                    let last_block = if batch.index == 0 {
+                        first_batch_last_block.clone()
+                    } else {
+                        None
+                    };
+                    let mut slots_processor = SlotsProcessor::new(
+                        context.clone(),
+                        last_block,
+                        cancellation_token.clone(),
+                    );
 
-            let handle = tokio::spawn(
-                async move {
-                    slots_processor
-                        .process_slots(thread_initial_slot, thread_final_slot)
-                        .await?;
+                    slots_processor.with_index_batching(index_batch_config);
 
-                    Ok(slots_processor.get_last_block())
+                    let synchronizer_batch_span = tracing::trace_span!(
+                        "synchronizer_batch",
+                        batch_initial_slot = batch.initial_slot,
+                        batch_final_slot = batch.final_slot
+                    );
+
+                    // Spawned on its own so a panic only affects the batch being processed
+                    let result = tokio::spawn(
+                        async move {
+                            slots_processor
+                                .process_slots(batch.initial_slot, batch.final_slot)
+                                .await?;
+
+                            Ok(slots_processor)
+                        }
+                        .instrument(synchronizer_batch_span),
+                    )
+                    .await;
+
+                    batch_results.push(SlotsBatchResult { batch, result });
                 }
-                .instrument(synchronizer_thread_span),
-            );
+
+                batch_results
+            });
 
             handles.push(handle);
         }
 
-        let handle_outputs = join_all(handles).await;
+        let mut batch_results = vec![];
+
+        for handle in join_all(handles).await {
+            match handle {
+                Ok(worker_batch_results) => batch_results.extend(worker_batch_results),
+                Err(error) => {
+                    return Err(anyhow!("Synchronizer worker panicked: {:?}", error).into());
+                }
+            }
+        }
+
+        batch_results.sort_by_key(|batch_result| batch_result.batch.index);
 
         let mut errors = vec![];
         let mut last_synced_block: Option<BlockData> = None;
+        let mut last_contiguous_slot: Option<u32> = None;
+        let mut is_contiguous = true;
+        let mut next_batch_index = 0;
+        // Processed blocks grouped in runs of consecutive slots whose continuity can be verified.
+        // In reverse sync, the chunk continues from the lowest block synced on the previous one,
+        // while in forward sync that edge is already checked by the first batch reorg detection.
+        let previous_chunk_block = if is_reverse_sync {
+            self.last_synced_block.clone()
+        } else {
+            None
+        };
+        let mut blocks_sequences: Vec<Vec<BlockData>> =
+            vec![previous_chunk_block.into_iter().collect()];
 
-        for handle in handle_outputs {
-            match handle {
-                Ok(thread_result) => match thread_result {
-                    Ok(thread_last_block) => {
-                        last_synced_block = thread_last_block;
+        for SlotsBatchResult { batch, result } in batch_results {
+            // Batches are missing when the synchronizer was cancelled before dispatching them
+            if batch.index != next_batch_index {
+                is_contiguous = false;
+                blocks_sequences.push(vec![]);
+            }
+
+            next_batch_index = batch.index + 1;
+
+            // Failed slots are saved to be retried, so the whole batch counts as handled
+            let batch_last_processed_slot = match result {
+                Ok(Ok(slots_processor)) => {
+                    if let Some(batch_last_block) = slots_processor.get_last_block() {
+                        last_synced_block = Some(batch_last_block);
                     }
-                    Err(error) => errors.push(error),
-                },
+
+                    if let Some(blocks_sequence) = blocks_sequences.last_mut() {
+                        blocks_sequence.extend_from_slice(slots_processor.get_processed_blocks());
+                    }
+
+                    if self.collect_pruned_blobs_slots {
+                        self.pruned_blobs_slots
+                            .extend_from_slice(slots_processor.get_pruned_blobs_slots());
+                    }
+
+                    if slots_processor.get_last_processed_slot() != Some(batch.final_slot) {
+                        blocks_sequences.push(vec![]);
+                    }
+
+                    slots_processor.get_last_processed_slot()
+                }
+                Ok(Err(error)) => {
+                    errors.push(error);
+                    blocks_sequences.push(vec![]);
+
+                    Some(batch.final_slot)
+                }
                 Err(error) => {
-                    let err = anyhow!("Synchronizer thread panicked: {:?}", error);
+                    let err = anyhow!("Synchronizer batch panicked: {:?}", error);
+
+                    errors.push(SlotsProcessorError::FailedSlotsProcessing {
+                        initial_slot: batch.initial_slot,
+                        final_slot: batch.final_slot,
+                        failed_slot: batch.initial_slot,
+                        error: SlotProcessingError::Other(err),
+                    });
+                    blocks_sequences.push(vec![]);
 
-                    errors.push(err.into());
+                    Some(batch.final_slot)
                 }
+            };
+
+            if is_contiguous {
+                if batch_last_processed_slot.is_some() {
+                    last_contiguous_slot = batch_last_processed_slot;
+                }
+
+                is_contiguous = batch_last_processed_slot == Some(batch.final_slot);
             }
         }
 
-        if errors.is_empty() {
-            self.last_synced_block = last_synced_block;
+        self._verify_continuity(blocks_sequences).await?;
 
-            Ok(())
+        if errors.is_empty() {
+            if last_synced_block.is_some() {
+                self.last_synced_block = last_synced_block;
+            }
         } else {
-            Err(SynchronizerError::FailedParallelSlotsProcessing {
+            // The failed slots leave a gap before the next chunk, so it can't be stitched to this
+            // one. Comparing against an older block would report a reorg that didn't happen
+            self.last_synced_block = None;
+
+            self._save_failed_slots_chunks(SynchronizerError::FailedParallelSlotsProcessing {
                 initial_slot: from_slot,
                 final_slot: to_slot,
                 chunk_errors: SlotsChunksErrors(errors),
             })
+            .await?;
         }
+
+        Ok(last_contiguous_slot)
     }
 
     async fn _sync_slots_by_checkpoints(
@@ -191,10 +518,14 @@ impl Synchronizer {
         );
 
         while unprocessed_slots > 0 {
+            if self.cancellation_token.is_cancelled() {
+                break;
+            }
+
             let slots_chunk = std::cmp::min(unprocessed_slots, self.slots_checkpoint);
             let initial_chunk_slot = current_slot;
             let final_chunk_slot = if is_reverse_sync {
-                current_slot - slots_chunk + 1
+                current_slot + 1 - slots_chunk
             } else {
                 current_slot + slots_chunk - 1
             };
@@ -205,39 +536,59 @@ impl Synchronizer {
                 final_slot = final_chunk_slot
             );
 
-            self._sync_slots(initial_chunk_slot, final_chunk_slot)
+            let last_slot = self
+                ._sync_slots(initial_chunk_slot, final_chunk_slot)
                 .instrument(sync_slots_chunk_span)
                 .await?;
 
-            let last_slot = Some(final_chunk_slot);
+            if last_slot.is_none() {
+                break;
+            }
+
+            self.last_synced_slot = last_slot;
+
             let last_lower_synced_slot = if is_reverse_sync { last_slot } else { None };
             let last_upper_synced_slot = if is_reverse_sync { None } else { last_slot };
 
-            if let Err(error) = self
-                .context
-                .blobscan_client()
-                .update_sync_state(BlockchainSyncState {
-                    last_lower_synced_slot,
-                    last_upper_synced_slot,
-                })
-                .await
-            {
-                let new_synced_slot = match last_lower_synced_slot {
-                    Some(slot) => slot,
-                    None => match last_upper_synced_slot {
+            if self.save_checkpoints {
+                if let Err(error) = self
+                    .context
+                    .blobscan_client()
+                    .update_sync_state(BlockchainSyncState {
+                        last_lower_synced_slot,
+                        last_upper_synced_slot,
+                        last_finalized_slot: None,
+                    })
+                    .await
+                {
+                    let new_synced_slot = match last_lower_synced_slot {
                         Some(slot) => slot,
-                        None => {
-                            return Err(SynchronizerError::Other(anyhow!(
-                                "Failed to get new last synced slot: last_lower_synced_slot and last_upper_synced_slot are both None"
-                            )))
-                        }
-                    },
-                };
+                        None => match last_upper_synced_slot {
+                            Some(slot) => slot,
+                            None => {
+                                return Err(SynchronizerError::Other(anyhow!(
+                                    "Failed to get new last synced slot: last_lower_synced_slot and last_upper_synced_slot are both None"
+                                )))
+                            }
+                        },
+                    };
 
-                return Err(SynchronizerError::FailedSlotCheckpointSave {
-                    slot: new_synced_slot,
-                    error,
-                });
+                    return Err(SynchronizerError::FailedSlotCheckpointSave {
+                        slot: new_synced_slot,
+                        error,
+                    });
+                }
+            }
+
+            if self.cancellation_token.is_cancelled() {
+                info!(
+                    target = "synchronizer",
+                    new_last_lower_synced_slot = last_lower_synced_slot,
+                    new_last_upper_synced_slot = last_upper_synced_slot,
+                    "Synchronizer cancelled. Last synced slot saved…"
+                );
+
+                break;
             }
 
             if unprocessed_slots >= self.slots_checkpoint {
@@ -249,8 +600,9 @@ impl Synchronizer {
                 );
             }
 
+            // Syncing back to genesis leaves no slot below the last chunk
             current_slot = if is_reverse_sync {
-                current_slot - slots_chunk
+                current_slot.saturating_sub(slots_chunk)
             } else {
                 current_slot + slots_chunk
             };
@@ -261,11 +613,130 @@ impl Synchronizer {
         Ok(())
     }
 
-    async fn _resolve_to_slot(&self, block_id: &BlockId) -> Result<u32, SynchronizerError> {
+    async fn _save_failed_slots_chunks(
+        &mut self,
+        error: SynchronizerError,
+    ) -> Result<(), SynchronizerError> {
+        let chunk_errors = match &error {
+            SynchronizerError::FailedParallelSlotsProcessing { chunk_errors, .. } => chunk_errors,
+            _ => return Err(error),
+        };
+
+        // Only the slots from the failed one onwards are left to be indexed
+        let failed_slots_chunks = match chunk_errors
+            .0
+            .iter()
+            .map(|chunk_error| match chunk_error {
+                SlotsProcessorError::FailedSlotsProcessing {
+                    final_slot,
+                    failed_slot,
+                    ..
+                } => Some(FailedSlotsChunk::from((*failed_slot, *final_slot))),
+                SlotsProcessorError::Other(_) => None,
+            })
+            .collect::<Option<Vec<FailedSlotsChunk>>>()
+        {
+            Some(chunks) => chunks,
+            None => return Err(error),
+        };
+
+        warn!(
+            target = "synchronizer",
+            %error,
+            ?failed_slots_chunks,
+            "Slots processing failed. Saving failed slots chunks to retry them later…"
+        );
+
+        self._store_failed_slots_chunks(failed_slots_chunks, error)
+            .await
+    }
+
+    /// Checks that every block's parent root matches the root of the previous processed block,
+    /// which isn't done by the slots processors at the edges of their batches nor at all when
+    /// syncing in reverse. Slots ranges that break the chain are saved to be re-indexed.
+    async fn _verify_continuity(
+        &mut self,
+        blocks_sequences: Vec<Vec<BlockData>>,
+    ) -> Result<(), SynchronizerError> {
+        let mut mismatched_slots_chunks = vec![];
+
+        for mut blocks in blocks_sequences {
+            blocks.sort_by_key(|block| block.slot);
+            // Blocks re-indexed when handling a reorg may have been processed twice
+            blocks.dedup_by(|block, previous_block| block.root == previous_block.root);
+
+            for pair in blocks.windows(2) {
+                let (block, next_block) = (&pair[0], &pair[1]);
+
+                if next_block.parent_root != block.root {
+                    warn!(
+                        target = "synchronizer",
+                        slot = next_block.slot,
+                        parent_root = ?next_block.parent_root,
+                        previous_slot = block.slot,
+                        previous_block_root = ?block.root,
+                        "Block parent root doesn't match the previous processed block"
+                    );
+
+                    mismatched_slots_chunks
+                        .push(FailedSlotsChunk::from((block.slot, next_block.slot)));
+                }
+            }
+        }
+
+        if mismatched_slots_chunks.is_empty() {
+            return Ok(());
+        }
+
+        let error = SynchronizerError::Other(anyhow!(
+            "Parent root continuity broken in {} slots ranges",
+            mismatched_slots_chunks.len()
+        ));
+
+        warn!(
+            target = "synchronizer",
+            failed_slots_chunks = ?mismatched_slots_chunks,
+            "Saving slots chunks with broken continuity to re-index them later…"
+        );
+
+        self._store_failed_slots_chunks(mismatched_slots_chunks, error)
+            .await
+    }
+
+    async fn _store_failed_slots_chunks(
+        &mut self,
+        failed_slots_chunks: Vec<FailedSlotsChunk>,
+        error: SynchronizerError,
+    ) -> Result<(), SynchronizerError> {
+        if !self.save_failed_slots_chunks {
+            self.failed_slots_chunks.extend(failed_slots_chunks);
+
+            return Ok(());
+        }
+
+        if let Err(store_error) = self
+            .context
+            .failed_slots_chunks_store()
+            .add(failed_slots_chunks.clone())
+            .await
+        {
+            return Err(SynchronizerError::FailedSlotsChunksSave {
+                error: store_error,
+                chunks_error: Box::new(error),
+            });
+        }
+
+        self.failed_slots_chunks.extend(failed_slots_chunks);
+
+        Ok(())
+    }
+
+    pub async fn resolve_to_slot(&self, block_id: &BlockId) -> Result<u32, SynchronizerError> {
         let beacon_client = self.context.beacon_client();
 
         let resolved_block_id: Result<u32, ClientError> = match block_id {
             BlockId::Slot(slot) => Ok(*slot),
+            BlockId::Genesis => Ok(0),
             _ => match beacon_client.get_block_header(block_id).await {
                 Ok(None) => {
                     let err = anyhow!("Block ID {} not found", block_id);
@@ -286,3 +757,372 @@ impl Synchronizer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{H256, U256, U64};
+    use serde_json::{json, Value};
+
+    use crate::utils::{
+        mock_server::{
+            beacon_node_handler, create_block, create_chain, create_forked_chain,
+            execution_block_hash, start_beacon_node, start_blobscan_api, start_execution_node,
+            start_server, state_root, Request, Response,
+        },
+        web3::calculate_versioned_hash,
+    };
+
+    use super::*;
+
+    /// Slots saved as checkpoints in the sync state requests sent to Blobscan.
+    fn get_checkpoints(requests: &[Request], field: &str) -> Vec<u64> {
+        requests
+            .iter()
+            .filter(|request| request.path == "/api/blockchain-sync-state")
+            .map(|request| {
+                let body = serde_json::from_slice::<Value>(&request.body).unwrap();
+
+                body[field].as_u64().unwrap()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_run() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let context = Context::for_nodes(
+            &start_beacon_node(create_chain(&[30, 31, 99])).await,
+            "http://127.0.0.1:1",
+            &blobscan_api_url,
+        );
+        let mut synchronizer = SynchronizerBuilder::new()
+            .with_num_threads(4)
+            .with_slots_checkpoint(50)
+            .build(context);
+
+        synchronizer
+            .run(&BlockId::Slot(0), &BlockId::Slot(99))
+            .await
+            .unwrap();
+
+        assert_eq!(synchronizer.get_last_synced_slot(), Some(99));
+        assert_eq!(synchronizer.last_synced_block.as_ref().unwrap().slot, 98);
+        assert!(synchronizer.get_failed_slots_chunks().is_empty());
+        assert_eq!(
+            get_checkpoints(&requests.lock().unwrap(), "lastUpperSyncedSlot"),
+            vec![49, 99]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_in_reverse() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let context = Context::for_nodes(
+            &start_beacon_node(create_chain(&[])).await,
+            "http://127.0.0.1:1",
+            &blobscan_api_url,
+        );
+        let mut synchronizer = SynchronizerBuilder::new()
+            .with_num_threads(4)
+            .with_slots_checkpoint(50)
+            .build(context);
+
+        synchronizer
+            .run(&BlockId::Head, &BlockId::Genesis)
+            .await
+            .unwrap();
+
+        assert_eq!(synchronizer.get_last_synced_slot(), Some(0));
+        assert_eq!(synchronizer.last_synced_block.as_ref().unwrap().slot, 0);
+        assert_eq!(
+            get_checkpoints(&requests.lock().unwrap(), "lastLowerSyncedSlot"),
+            vec![50, 0]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_with_failed_slot() {
+        let handler = beacon_node_handler(create_chain(&[]));
+        let beacon_node_url = start_server(move |request| {
+            if request.path == "/eth/v1/beacon/headers/45" {
+                return Response::error(500);
+            }
+
+            handler(request)
+        })
+        .await;
+        let mut synchronizer = SynchronizerBuilder::new()
+            .with_num_threads(4)
+            .with_checkpoints_saving(false)
+            .with_failed_slots_chunks_saving(false)
+            .build(Context::for_beacon_node(&beacon_node_url));
+
+        synchronizer
+            .run(&BlockId::Slot(0), &BlockId::Slot(99))
+            .await
+            .unwrap();
+
+        // The failed slots are left to be retried, so the rest of them are still synced
+        assert_eq!(synchronizer.get_last_synced_slot(), Some(99));
+        assert!(synchronizer.last_synced_block.is_none());
+        assert_eq!(
+            synchronizer.get_failed_slots_chunks(),
+            [FailedSlotsChunk::from((45, 59))]
+        );
+    }
+
+    fn create_reorg_event(old_head_block: u64, new_head_block: u64) -> ChainReorgEventData {
+        ChainReorgEventData {
+            slot: 13,
+            depth: 2,
+            old_head_block: H256::from_low_u64_be(old_head_block),
+            new_head_block: H256::from_low_u64_be(new_head_block),
+            old_head_state: H256::zero(),
+            new_head_state: H256::zero(),
+        }
+    }
+
+    /// Creates a chain from 0 to 99 whose blocks at the given slots don't descend from the
+    /// previous one.
+    fn create_broken_chain(broken_slots: &[u32]) -> Vec<BlockData> {
+        create_chain(&[])
+            .into_iter()
+            .map(|block| {
+                if broken_slots.contains(&block.slot) {
+                    create_block(block.slot, block.root.to_low_u64_be(), 999)
+                } else {
+                    block
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_run_with_broken_continuity_between_batches() {
+        let mut synchronizer = SynchronizerBuilder::new()
+            .with_num_threads(4)
+            .with_checkpoints_saving(false)
+            .with_failed_slots_chunks_saving(false)
+            .build(Context::for_beacon_node(
+                &start_beacon_node(create_broken_chain(&[40])).await,
+            ));
+
+        synchronizer
+            .run(&BlockId::Slot(0), &BlockId::Slot(99))
+            .await
+            .unwrap();
+
+        assert_eq!(synchronizer.get_last_synced_slot(), Some(99));
+        assert_eq!(
+            synchronizer.get_failed_slots_chunks(),
+            [FailedSlotsChunk::from((39, 40))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_in_reverse_with_broken_continuity() {
+        let mut synchronizer = SynchronizerBuilder::new()
+            .with_num_threads(4)
+            .with_slots_checkpoint(50)
+            .with_checkpoints_saving(false)
+            .with_failed_slots_chunks_saving(false)
+            .build(Context::for_beacon_node(
+                &start_beacon_node(create_broken_chain(&[75, 50])).await,
+            ));
+
+        synchronizer
+            .run(&BlockId::Slot(99), &BlockId::Slot(0))
+            .await
+            .unwrap();
+
+        // Slot 50 is the last one synced before the checkpoint, so it's checked against the
+        // first block of the next chunk
+        assert_eq!(synchronizer.get_last_synced_slot(), Some(0));
+        assert_eq!(
+            synchronizer.get_failed_slots_chunks(),
+            [
+                FailedSlotsChunk::from((74, 75)),
+                FailedSlotsChunk::from((49, 50))
+            ]
+        );
+    }
+
+    /// Starts a beacon node and an execution node where the block at slot 5 has a blob whose
+    /// sidecar has been pruned, and returns their URLs.
+    async fn start_nodes_with_pruned_blobs() -> (String, String) {
+        const COMMITMENT: &str = "0x95775c4349d5b03e71bba6452d79b79a88842c924ac480042fc7c20e8e5a28068eac650dc8fc9789c6515ca84b7514ef";
+
+        let block_root = H256::from_low_u64_be(6);
+        let handler = beacon_node_handler(create_chain(&[]));
+        let beacon_node_url = start_server(move |request| {
+            if request.path == "/eth/v2/beacon/blocks/5" {
+                return Response::json(json!({
+                    "version": "deneb",
+                    "data": {
+                        "message": {
+                            "slot": "5",
+                            "parent_root": H256::from_low_u64_be(5),
+                            "state_root": state_root(block_root),
+                            "body": {
+                                "execution_payload": {
+                                    "block_hash": execution_block_hash(block_root)
+                                },
+                                "blob_kzg_commitments": [COMMITMENT]
+                            }
+                        }
+                    }
+                }));
+            }
+
+            handler(request)
+        })
+        .await;
+
+        let mut tx = serde_json::to_value(ethers::types::Transaction {
+            gas_price: Some(U256::one()),
+            ..Default::default()
+        })
+        .unwrap();
+        let mut block = serde_json::to_value(ethers::types::Block::<Value> {
+            hash: Some(execution_block_hash(block_root)),
+            number: Some(U64::from(5)),
+            ..Default::default()
+        })
+        .unwrap();
+
+        tx["maxFeePerBlobGas"] = json!("0x1");
+        tx["blobVersionedHashes"] = json!([calculate_versioned_hash(COMMITMENT).unwrap()]);
+        block["blobGasUsed"] = json!("0x20000");
+        block["excessBlobGas"] = json!("0x0");
+        block["transactions"] = json!([tx]);
+
+        (beacon_node_url, start_execution_node(Some(block)).await)
+    }
+
+    async fn get_pruned_blobs_slots(collect_pruned_blobs_slots: bool) -> Vec<u32> {
+        let (blobscan_api_url, _) = start_blobscan_api().await;
+        let (beacon_node_url, execution_node_url) = start_nodes_with_pruned_blobs().await;
+        let context = Context::for_nodes(&beacon_node_url, &execution_node_url, &blobscan_api_url);
+        let mut synchronizer = SynchronizerBuilder::new()
+            .with_pruned_blobs_slots_collection(collect_pruned_blobs_slots)
+            .build(context);
+
+        synchronizer
+            .run(&BlockId::Slot(0), &BlockId::Slot(9))
+            .await
+            .unwrap();
+
+        assert_eq!(synchronizer.get_last_synced_slot(), Some(9));
+
+        synchronizer.get_pruned_blobs_slots().to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_run_with_pruned_blobs() {
+        assert_eq!(get_pruned_blobs_slots(true).await, vec![5]);
+    }
+
+    #[tokio::test]
+    async fn test_run_without_pruned_blobs_slots_collection() {
+        assert!(get_pruned_blobs_slots(false).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_chain_reorg() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let context = Context::for_nodes(
+            &start_beacon_node(create_forked_chain()).await,
+            "http://127.0.0.1:1",
+            &blobscan_api_url,
+        );
+        let mut synchronizer = SynchronizerBuilder::new().build(context);
+
+        synchronizer.last_synced_block = Some(create_block(12, 112, 111));
+        synchronizer
+            .handle_chain_reorg(&create_reorg_event(112, 213))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            synchronizer.last_synced_block.unwrap().root,
+            H256::from_low_u64_be(213)
+        );
+
+        let requests = requests.lock().unwrap();
+
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "PUT");
+        assert_eq!(requests[0].path, "/api/indexer/reorged-blocks");
+
+        let body = serde_json::from_slice::<Value>(&requests[0].body).unwrap();
+
+        assert_eq!(body["newHeadBlockRoot"], json!(H256::from_low_u64_be(213)));
+        assert_eq!(
+            body["reorgedBlocks"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|block| block["slot"].as_u64().unwrap())
+                .collect::<Vec<_>>(),
+            vec![12, 11]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_chain_reorg_without_synced_block() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let context = Context::for_nodes(
+            &start_beacon_node(create_forked_chain()).await,
+            "http://127.0.0.1:1",
+            &blobscan_api_url,
+        );
+        let mut synchronizer = SynchronizerBuilder::new().build(context);
+
+        synchronizer
+            .handle_chain_reorg(&create_reorg_event(112, 213))
+            .await
+            .unwrap();
+
+        assert!(synchronizer.last_synced_block.is_none());
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_chain_reorg_with_new_head_already_synced() {
+        let (blobscan_api_url, requests) = start_blobscan_api().await;
+        let context = Context::for_nodes(
+            &start_beacon_node(create_forked_chain()).await,
+            "http://127.0.0.1:1",
+            &blobscan_api_url,
+        );
+        let mut synchronizer = SynchronizerBuilder::new().build(context);
+
+        synchronizer.last_synced_block = Some(create_block(13, 213, 212));
+        synchronizer
+            .handle_chain_reorg(&create_reorg_event(112, 213))
+            .await
+            .unwrap();
+
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_chain_reorg_with_missing_new_head() {
+        let (blobscan_api_url, _) = start_blobscan_api().await;
+        let context = Context::for_nodes(
+            &start_beacon_node(create_forked_chain()).await,
+            "http://127.0.0.1:1",
+            &blobscan_api_url,
+        );
+        let mut synchronizer = SynchronizerBuilder::new().build(context);
+
+        synchronizer.last_synced_block = Some(create_block(12, 112, 111));
+
+        assert!(matches!(
+            synchronizer
+                .handle_chain_reorg(&create_reorg_event(112, 999))
+                .await,
+            Err(SynchronizerError::FailedReorgHandling { .. })
+        ));
+    }
+}
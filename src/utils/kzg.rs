@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use c_kzg::{ethereum_kzg_settings, Blob, Bytes48, KzgProof};
+
+use crate::clients::beacon::types::Blob as BeaconBlob;
+
+/// Checks the blob against its KZG commitment and proof using the Ethereum trusted setup.
+pub fn verify_blob_kzg_proof(blob: &BeaconBlob) -> Result<bool> {
+    let blob_data = Blob::from_bytes(&blob.blob).context("Invalid blob data")?;
+    let commitment = Bytes48::from_hex(&blob.kzg_commitment).context("Invalid KZG commitment")?;
+    let proof = Bytes48::from_hex(&blob.kzg_proof).context("Invalid KZG proof")?;
+
+    KzgProof::verify_blob_kzg_proof(&blob_data, &commitment, &proof, ethereum_kzg_settings())
+        .context("Failed to verify blob KZG proof")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clients::beacon::types::BlobsResponse;
+
+    use super::*;
+
+    fn load_blob() -> BeaconBlob {
+        let response: BlobsResponse = serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/blob_sidecar.json"
+        )))
+        .unwrap();
+
+        response.data.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn test_verify_blob_kzg_proof() {
+        assert!(verify_blob_kzg_proof(&load_blob()).unwrap());
+    }
+
+    #[test]
+    fn test_verify_blob_kzg_proof_with_tampered_blob() {
+        let mut blob = load_blob();
+        let mut data = blob.blob.to_vec();
+
+        // Keep the field element canonical by only touching its lowest byte
+        data[31] ^= 1;
+        blob.blob = data.into();
+
+        assert!(!verify_blob_kzg_proof(&blob).unwrap());
+    }
+
+    #[test]
+    fn test_verify_blob_kzg_proof_with_other_proof() {
+        let mut blob = load_blob();
+
+        blob.kzg_proof = blob.kzg_commitment.clone();
+
+        assert!(!verify_blob_kzg_proof(&blob).unwrap());
+    }
+
+    #[test]
+    fn test_verify_blob_kzg_proof_with_invalid_input() {
+        let mut blob = load_blob();
+
+        blob.blob = blob.blob[1..].to_vec().into();
+
+        assert!(verify_blob_kzg_proof(&blob).is_err());
+
+        let mut blob = load_blob();
+
+        blob.kzg_commitment = "0x1234".to_string();
+
+        assert!(verify_blob_kzg_proof(&blob).is_err());
+    }
+}
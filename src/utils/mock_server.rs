@@ -0,0 +1,341 @@
+use std::sync::{Arc, Mutex};
+
+use ethers::types::H256;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::slots_processor::BlockData;
+
+/// Request received by a [`start_server`] handler.
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    /// Path of the request, including its query string.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(body: impl Serialize) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: serde_json::to_vec(&body).unwrap(),
+        }
+    }
+
+    /// SSZ encoded response of a resource of the given fork.
+    pub fn ssz(body: &[u8], consensus_version: &str) -> Self {
+        Self {
+            status: 200,
+            headers: vec![
+                (
+                    "Content-Type".to_string(),
+                    "application/octet-stream".to_string(),
+                ),
+                (
+                    "Eth-Consensus-Version".to_string(),
+                    consensus_version.to_string(),
+                ),
+            ],
+            body: body.to_vec(),
+        }
+    }
+
+    pub fn not_found() -> Self {
+        Self::error(404)
+    }
+
+    pub fn error(status: u16) -> Self {
+        Self {
+            status,
+            ..Self::json(serde_json::json!({ "code": status, "message": "Mock server error" }))
+        }
+    }
+}
+
+/// Starts an HTTP server on a random local port answering every request with the response
+/// built by the handler, and returns its base URL. It stands in for the nodes and APIs the
+/// indexer talks to.
+pub async fn start_server<F>(handler: F) -> String
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let handler = handler.clone();
+
+            tokio::spawn(async move {
+                if let Some(request) = read_request(&mut stream).await {
+                    write_response(&mut stream, handler(request)).await;
+                }
+            });
+        }
+    });
+
+    base_url
+}
+
+pub fn create_block(slot: u32, root: u64, parent_root: u64) -> BlockData {
+    BlockData {
+        root: H256::from_low_u64_be(root),
+        parent_root: H256::from_low_u64_be(parent_root),
+        slot,
+    }
+}
+
+/// Chain forked after the block at slot 10 (root 10). The old branch has blocks 111 and 112
+/// at slots 11 and 12, while the new one skips slot 11 and has blocks 212 and 213 at slots 12
+/// and 13.
+pub fn create_forked_chain() -> Vec<BlockData> {
+    vec![
+        create_block(10, 10, 9),
+        create_block(11, 111, 10),
+        create_block(12, 112, 111),
+        create_block(12, 212, 10),
+        create_block(13, 213, 212),
+    ]
+}
+
+/// Creates a chain with a block for every slot from 0 to 99 but the skipped ones. The root of
+/// each block is its slot plus one.
+pub fn create_chain(skipped_slots: &[u32]) -> Vec<BlockData> {
+    let mut parent_root = 0;
+
+    (0..100)
+        .filter(|slot| !skipped_slots.contains(slot))
+        .map(|slot| {
+            let block = create_block(slot, u64::from(slot) + 1, parent_root);
+
+            parent_root = block.root.to_low_u64_be();
+
+            block
+        })
+        .collect()
+}
+
+/// Starts a synced beacon node serving the headers and blocks of the given blocks by root or
+/// slot, plus the one with the highest slot as `head`. When several blocks share a slot, the last
+/// one given is the one served for it. Blocks carry no blobs, and their state root and execution
+/// block hash are given by [`state_root`] and [`execution_block_hash`].
+pub async fn start_beacon_node(blocks: Vec<BlockData>) -> String {
+    start_server(beacon_node_handler(blocks)).await
+}
+
+/// Handler of the beacon node started by [`start_beacon_node`], so it can be wrapped to
+/// override some of its responses.
+pub fn beacon_node_handler(
+    blocks: Vec<BlockData>,
+) -> impl Fn(Request) -> Response + Send + Sync + 'static {
+    move |request| {
+        if request.path == "/eth/v1/node/syncing" {
+            let head_slot = blocks.iter().map(|block| block.slot).max().unwrap_or(0);
+
+            return Response::json(json!({
+                "data": {
+                    "head_slot": head_slot.to_string(),
+                    "sync_distance": "0",
+                    "is_syncing": false
+                }
+            }));
+        }
+
+        let (resource, block_id) = match request.path.rsplit_once('/') {
+            Some(parts) => parts,
+            None => return Response::not_found(),
+        };
+        let block = match block_id {
+            "head" => blocks.iter().max_by_key(|block| block.slot),
+            _ => blocks.iter().rev().find(|block| {
+                format!("{:#x}", block.root) == block_id || block.slot.to_string() == block_id
+            }),
+        };
+        let Some(block) = block else {
+            return Response::not_found();
+        };
+
+        match resource {
+            "/eth/v1/beacon/headers" => Response::json(json!({
+                "data": {
+                    "root": block.root,
+                    "canonical": true,
+                    "header": {
+                        "message": {
+                            "slot": block.slot.to_string(),
+                            "proposer_index": "0",
+                            "parent_root": block.parent_root,
+                            "state_root": state_root(block.root),
+                            "body_root": H256::zero()
+                        }
+                    }
+                }
+            })),
+            "/eth/v2/beacon/blocks" => Response::json(json!({
+                "version": "deneb",
+                "data": {
+                    "message": {
+                        "slot": block.slot.to_string(),
+                        "parent_root": block.parent_root,
+                        "state_root": state_root(block.root),
+                        "body": {
+                            "execution_payload": {
+                                "block_hash": execution_block_hash(block.root)
+                            },
+                            "blob_kzg_commitments": []
+                        }
+                    }
+                }
+            })),
+            _ => Response::not_found(),
+        }
+    }
+}
+
+/// Starts an execution node answering every JSON-RPC request with the given result, or with an
+/// error when there's none.
+pub async fn start_execution_node(result: Option<Value>) -> String {
+    start_server(move |request| {
+        let id = serde_json::from_slice::<Value>(&request.body).unwrap()["id"].clone();
+
+        Response::json(match &result {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32000, "message": "internal error" }
+            }),
+        })
+    })
+    .await
+}
+
+/// Starts an empty Blobscan API accepting every write request, and returns its base URL along
+/// with the requests it receives.
+pub async fn start_blobscan_api() -> (String, Arc<Mutex<Vec<Request>>>) {
+    let requests = Arc::new(Mutex::new(vec![]));
+    let received_requests = requests.clone();
+    let base_url = start_server(move |request| {
+        let is_read = request.method == "GET";
+
+        received_requests.lock().unwrap().push(request);
+
+        if is_read {
+            return Response::not_found();
+        }
+
+        Response {
+            status: 200,
+            headers: vec![],
+            body: vec![],
+        }
+    })
+    .await;
+
+    (base_url, requests)
+}
+
+/// State root of the blocks served by [`start_beacon_node`]: the block root with its first byte
+/// set to `0x57`.
+pub fn state_root(block_root: H256) -> H256 {
+    let mut state_root = block_root;
+
+    state_root.0[0] = 0x57;
+
+    state_root
+}
+
+/// Execution block hash of the blocks served by [`start_beacon_node`]: the block root with its
+/// first byte set to `0xee`.
+pub fn execution_block_hash(block_root: H256) -> H256 {
+    let mut block_hash = block_root;
+
+    block_hash.0[0] = 0xee;
+
+    block_hash
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut data = vec![];
+    let mut buffer = [0; 8192];
+
+    loop {
+        let read = stream.read(&mut buffer).await.ok()?;
+
+        if read == 0 {
+            return None;
+        }
+
+        data.extend_from_slice(&buffer[..read]);
+
+        let Some(head_end) = data.windows(4).position(|window| window == b"\r\n\r\n") else {
+            continue;
+        };
+        let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+        let mut lines = head.lines();
+        let mut request_line = lines.next()?.split_whitespace();
+        let method = request_line.next()?.to_string();
+        let path = request_line.next()?.to_string();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect::<Vec<_>>();
+        let content_length = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.parse::<usize>().ok())
+            .unwrap_or(0);
+        let body = &data[head_end + 4..];
+
+        if body.len() >= content_length {
+            return Some(Request {
+                method,
+                path,
+                headers,
+                body: body.to_vec(),
+            });
+        }
+    }
+}
+
+async fn write_response(stream: &mut TcpStream, response: Response) {
+    let mut head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+
+    for (name, value) in response.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+
+    head.push_str("\r\n");
+
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(&response.body).await;
+}
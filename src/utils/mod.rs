@@ -1,2 +1,6 @@
+pub mod kzg;
+#[cfg(test)]
+pub mod mock_server;
+pub mod ssz;
 pub mod telemetry;
 pub mod web3;
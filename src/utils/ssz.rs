@@ -0,0 +1,133 @@
+use ethers::core::k256::sha2::{Digest, Sha256};
+use ethers::types::H256;
+
+pub fn hash_pair(left: &[u8], right: &[u8]) -> H256 {
+    let mut hasher = Sha256::new();
+
+    hasher.update(left);
+    hasher.update(right);
+
+    H256::from_slice(&hasher.finalize())
+}
+
+/// Packs an `uint64` into a chunk, as SSZ encodes basic types in little-endian.
+pub fn uint64_chunk(value: u64) -> H256 {
+    let mut chunk = [0u8; 32];
+
+    chunk[..8].copy_from_slice(&value.to_le_bytes());
+
+    H256::from(chunk)
+}
+
+/// Hash tree root of a fixed-size byte vector, padded with zeros to a multiple of 32 bytes.
+pub fn bytes_root(bytes: &[u8]) -> H256 {
+    let chunks = bytes
+        .chunks(32)
+        .map(|chunk| {
+            let mut padded_chunk = [0u8; 32];
+
+            padded_chunk[..chunk.len()].copy_from_slice(chunk);
+
+            H256::from(padded_chunk)
+        })
+        .collect::<Vec<_>>();
+
+    merkleize(&chunks)
+}
+
+/// Merkle root of the given chunks, padded with zero chunks up to the next power of two.
+pub fn merkleize(chunks: &[H256]) -> H256 {
+    let mut layer = chunks.to_vec();
+
+    layer.resize(chunks.len().next_power_of_two().max(1), H256::zero());
+
+    while layer.len() > 1 {
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(pair[0].as_bytes(), pair[1].as_bytes()))
+            .collect();
+    }
+
+    layer[0]
+}
+
+/// Checks that `leaf` is at `index` of the tree of the given `depth` and `root`.
+pub fn is_valid_merkle_branch(
+    leaf: H256,
+    branch: &[H256],
+    depth: usize,
+    index: u64,
+    root: H256,
+) -> bool {
+    if branch.len() != depth {
+        return false;
+    }
+
+    let value = branch.iter().enumerate().fold(leaf, |value, (i, node)| {
+        if (index >> i) & 1 == 1 {
+            hash_pair(node.as_bytes(), value.as_bytes())
+        } else {
+            hash_pair(value.as_bytes(), node.as_bytes())
+        }
+    });
+
+    value == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(byte: u8) -> H256 {
+        H256::repeat_byte(byte)
+    }
+
+    #[test]
+    fn test_uint64_chunk() {
+        assert_eq!(
+            uint64_chunk(0x0102),
+            "0x0201000000000000000000000000000000000000000000000000000000000000"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_merkleize() {
+        let pair_root = hash_pair(chunk(1).as_bytes(), chunk(2).as_bytes());
+        let padded_pair_root = hash_pair(chunk(3).as_bytes(), H256::zero().as_bytes());
+
+        assert_eq!(merkleize(&[]), H256::zero());
+        assert_eq!(merkleize(&[chunk(1)]), chunk(1));
+        assert_eq!(merkleize(&[chunk(1), chunk(2)]), pair_root);
+        assert_eq!(
+            merkleize(&[chunk(1), chunk(2), chunk(3)]),
+            hash_pair(pair_root.as_bytes(), padded_pair_root.as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_bytes_root() {
+        let bytes = [7u8; 48];
+        let mut padded_chunk = [0u8; 32];
+
+        padded_chunk[..16].copy_from_slice(&bytes[32..]);
+
+        assert_eq!(bytes_root(&bytes), hash_pair(&bytes[..32], &padded_chunk));
+    }
+
+    #[test]
+    fn test_is_valid_merkle_branch() {
+        let leaves = [chunk(1), chunk(2), chunk(3), chunk(4)];
+        let root = merkleize(&leaves);
+        let branch = [
+            leaves[3],
+            hash_pair(leaves[0].as_bytes(), leaves[1].as_bytes()),
+        ];
+
+        assert!(is_valid_merkle_branch(leaves[2], &branch, 2, 2, root));
+        assert!(!is_valid_merkle_branch(leaves[2], &branch, 2, 3, root));
+        assert!(!is_valid_merkle_branch(leaves[3], &branch, 2, 2, root));
+        assert!(!is_valid_merkle_branch(leaves[2], &branch[..1], 1, 2, root));
+    }
+}